@@ -4,3 +4,18 @@ Feature: flt version
     When the command `flt version` is run
     Then it should exit with status code 0
     And the output should contain "flt version 0.0.1"
+
+  Scenario: flt eval
+    When the command `flt eval 1 + 2 * 3` is run
+    Then it should exit with status code 0
+    And the output should contain "7"
+
+  Scenario: flt eval with let bindings
+    When the command `flt eval let x = 2; let y = x * 3; y + 1` is run
+    Then it should exit with status code 0
+    And the output should contain "7"
+
+  Scenario: flt eval with a parse error
+    When the command `flt eval 1 + 2 )` is run
+    Then it should exit with status code 1
+    And the error output should contain "expected end of input, found ')' at line 1, column 7"
@@ -1,9 +1,52 @@
+use clap::Parser;
+use clap::Subcommand;
+
+use flt::eval::eval_program;
+use flt::eval::Environment;
+
+#[derive(Parser)]
+#[command(about = "a 'lite' functional language")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the version
+    Version,
+    /// Evaluate a program and print the value of its last statement
+    Eval {
+        /// The program; multiple arguments are joined with spaces
+        #[arg(required = true, allow_hyphen_values = true)]
+        expr: Vec<String>,
+    },
+}
+
 // Returns the library version, which reflects the crate version
 pub fn version() -> String {
     clap::crate_version!().to_string()
 }
 
+// Parses and evaluates `input`, returning the printed form of its value
+fn run_eval(input: &str) -> eyre::Result<String> {
+    let program = flt::parse_program(input)?;
+    let value = eval_program(&program, &Environment::new())?;
+    Ok(value.to_string())
+}
+
 // Main function
 fn main() {
-    println!("flt version {}", version());
+    env_logger::init();
+    let cli = Cli::parse();
+    match cli.command {
+        None | Some(Command::Version) => println!("flt version {}", version()),
+        Some(Command::Eval { expr }) => match run_eval(&expr.join(" ")) {
+            Ok(value) => println!("{}", value),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+    }
 }
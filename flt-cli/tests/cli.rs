@@ -9,6 +9,7 @@ use cucumber::World;
 pub struct CliWorld {
     pub status: Option<i32>,
     pub output: Option<String>,
+    pub error_output: Option<String>,
 }
 
 #[when(regex = r#"^the command `(.+)` is run$"#)]
@@ -32,6 +33,7 @@ async fn the_command_is_run(world: &mut CliWorld, cmd: String) {
             let output_str = String::from_utf8(output.stdout).unwrap();
             println!("Output: {}", output_str);
             world.output = Some(output_str);
+            world.error_output = Some(String::from_utf8(output.stderr).unwrap());
         }
         Err(e) => {
             panic!("Failed to run command: {}", e);
@@ -55,6 +57,16 @@ async fn the_output_should_contain(world: &mut CliWorld, expected: String) {
     );
 }
 
+#[then(expr = r"the error output should contain {string}")]
+async fn the_error_output_should_contain(world: &mut CliWorld, expected: String) {
+    assert!(world.error_output.is_some(), "No error output");
+    assert!(
+        world.error_output.as_ref().unwrap().contains(&expected),
+        "Error output does not contain {}",
+        expected
+    );
+}
+
 #[tokio::main]
 async fn main() {
     env_logger::builder()
@@ -5,10 +5,26 @@ mod identifier;
 mod literal;
 mod number;
 mod operands;
+mod pattern;
+mod program;
+mod regex;
+mod span;
+mod statement;
+mod trivia;
 
 pub use expr::Expr;
+pub use expr::ExprKind;
+pub use expr::StringPart;
 pub use identifier::Identifier;
 pub use literal::Literal;
 pub use number::Numeric;
 pub use operands::BinaryOp;
 pub use operands::UnaryOp;
+pub use pattern::Pattern;
+pub use program::Program;
+pub use regex::Regex;
+pub use span::Span;
+pub use statement::Statement;
+pub use statement::StatementKind;
+pub use trivia::Comment;
+pub use trivia::Trivia;
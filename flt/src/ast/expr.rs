@@ -4,10 +4,29 @@ use super::identifier::Identifier;
 use super::literal::Literal;
 use super::operands::BinaryOp;
 use super::operands::UnaryOp;
+use super::pattern::Pattern;
+use super::span::Span;
+use super::trivia::Trivia;
 
-/// An expression in the language.
+/// An expression in the language: what kind of expression it is, where it appears in the source,
+/// and the comments around it.
+#[derive(Clone, Debug)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+    pub trivia: Trivia,
+}
+
+/// Expressions are equal when they have the same kind and trivia, wherever they appear.
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.trivia == other.trivia
+    }
+}
+
+/// The kinds of expression in the language.
 #[derive(Clone, Debug, PartialEq)]
-pub enum Expr {
+pub enum ExprKind {
     /// A literal value (number, string, or boolean).
     Literal(Literal),
     /// An identifier.
@@ -16,46 +35,93 @@ pub enum Expr {
     UnaryExpr(UnaryOp, Box<Expr>),
     /// A binary expression with left, operator, and right operands.
     BinaryExpr(Box<Expr>, BinaryOp, Box<Expr>),
-    /// A function call: name and arguments.
-    FunctionCall(Identifier, Vec<Expr>),
+    /// A function call: name, positional arguments, and named arguments (`name: expr`).
+    FunctionCall(Identifier, Vec<Expr>, Vec<(Identifier, Expr)>),
+    /// A call of the function an expression evaluates to: the callee, positional arguments, and
+    /// named arguments (`(fn(x) => x + 1)(2)`, `make()(1)`).
+    Call(Box<Expr>, Vec<Expr>, Vec<(Identifier, Expr)>),
     /// A parenthesized expression.
     Parenthesized(Box<Expr>),
+    /// An anonymous function: parameters and body (`fn(x, y) => x + y`).
+    Lambda(Vec<Identifier>, Box<Expr>),
+    /// A list: `[a, b, c]`.
+    List(Vec<Expr>),
+    /// Indexing: `xs[i]`.
+    Index(Box<Expr>, Box<Expr>),
+    /// Slicing: `xs[a:b]`, where either bound may be omitted.
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    /// A range of integers: start, end, whether the end is included, and an optional step
+    /// (`0..10`, `1..=5`, `0..100 step 10`).
+    Range(Box<Expr>, Box<Expr>, bool, Option<Box<Expr>>),
+    /// A record: `{name: "x", "order id": 3}`. Keys are unique and keep their order.
+    Record(Vec<(Identifier, Expr)>),
+    /// Field access: `rec.name` or `rec."order id"`.
+    Field(Box<Expr>, Identifier),
+    /// Null-safe field access: `rec?.name`, which is null when `rec` is null.
+    SafeField(Box<Expr>, Identifier),
+    /// A conditional: `if cond then a else b`.
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// A string with embedded expressions: `"rows: ${count}"`.
+    Interpolation(Vec<StringPart>),
+    /// A match: the value matched and its arms, tried in order (`match x { :ok => 1, _ => 0 }`).
+    Match(Box<Expr>, Vec<(Pattern, Expr)>),
+}
+
+/// A piece of an interpolated string: text, or an expression whose value is formatted into it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StringPart {
+    Text(String),
+    Expr(Expr),
 }
 
 impl Expr {
+    /// Constructs an expression of the given kind located at `span`.
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Expr {
+            kind,
+            span,
+            trivia: Trivia::default(),
+        }
+    }
+
+    /// Constructs a literal expression.
+    pub fn literal(literal: Literal) -> Self {
+        ExprKind::Literal(literal).into()
+    }
+
     /// Constructs a boolean literal expression.
     pub fn literal_boolean(b: bool) -> Self {
-        Expr::Literal(Literal::boolean(b))
+        Expr::literal(Literal::boolean(b))
     }
 
     /// Constructs a symbol literal expression (e.g. `:foo` or `:"hello"`).
     pub fn literal_symbol(s: impl Into<String>) -> Self {
-        Expr::Literal(Literal::symbol(s))
+        Expr::literal(Literal::symbol(s))
     }
 
     /// Constructs a string literal expression (e.g. `"hello"`).
     pub fn literal_string(s: impl Into<String>) -> Self {
-        Expr::Literal(Literal::string(s))
+        Expr::literal(Literal::string(s))
     }
 
     /// Constructs a number literal expression from a string (e.g. `"3.14"`).
     pub fn literal_number(n: impl Into<BigDecimal>) -> Self {
-        Expr::Literal(Literal::number(n))
+        Expr::literal(Literal::number(n))
     }
 
     /// Constructs an identifier expression.
     pub fn ident(s: impl Into<String>) -> Self {
-        Expr::Ident(s.into())
+        ExprKind::Ident(s.into()).into()
     }
 
     /// Constructs a unary expression.
     pub fn unary_expr(op: UnaryOp, expr: Expr) -> Self {
-        Expr::UnaryExpr(op, Box::new(expr))
+        ExprKind::UnaryExpr(op, Box::new(expr)).into()
     }
 
     /// Constructs a binary expression.
     pub fn binary_expr(left: Expr, op: BinaryOp, right: Expr) -> Self {
-        Expr::BinaryExpr(Box::new(left), op, Box::new(right))
+        ExprKind::BinaryExpr(Box::new(left), op, Box::new(right)).into()
     }
 
     /// Constructs a function call expression.
@@ -63,11 +129,184 @@ impl Expr {
         name: impl TryInto<Identifier, Error = crate::Error>,
         args: Vec<Expr>,
     ) -> Self {
-        Expr::FunctionCall(name.try_into().expect("failed to convert identifier"), args)
+        Expr::function_call_named(name, args, vec![])
+    }
+
+    /// Constructs a function call expression with named arguments (e.g. `WRITE("out", pretty: true)`).
+    pub fn function_call_named(
+        name: impl TryInto<Identifier, Error = crate::Error>,
+        args: Vec<Expr>,
+        named: Vec<(&str, Expr)>,
+    ) -> Self {
+        let named = named
+            .into_iter()
+            .map(|(n, e)| {
+                (
+                    Identifier::try_from(n).expect("failed to convert identifier"),
+                    e,
+                )
+            })
+            .collect();
+        ExprKind::FunctionCall(
+            name.try_into().expect("failed to convert identifier"),
+            args,
+            named,
+        )
+        .into()
+    }
+
+    /// Constructs a call of the function that `callee` evaluates to.
+    pub fn call(callee: Expr, args: Vec<Expr>) -> Self {
+        ExprKind::Call(Box::new(callee), args, vec![]).into()
     }
 
     /// Constructs a parenthesized expression.
     pub fn parenthesized(expr: Expr) -> Self {
-        Expr::Parenthesized(Box::new(expr))
+        ExprKind::Parenthesized(Box::new(expr)).into()
+    }
+
+    /// Constructs a lambda expression.
+    pub fn lambda(params: Vec<&str>, body: Expr) -> Self {
+        let params = params
+            .into_iter()
+            .map(|p| Identifier::try_from(p).expect("failed to convert identifier"))
+            .collect();
+        ExprKind::Lambda(params, Box::new(body)).into()
+    }
+
+    /// Constructs a list expression.
+    pub fn list(items: Vec<Expr>) -> Self {
+        ExprKind::List(items).into()
+    }
+
+    /// Constructs an index expression.
+    pub fn index(target: Expr, index: Expr) -> Self {
+        ExprKind::Index(Box::new(target), Box::new(index)).into()
+    }
+
+    /// Constructs a slice expression.
+    pub fn slice(target: Expr, start: Option<Expr>, end: Option<Expr>) -> Self {
+        ExprKind::Slice(Box::new(target), start.map(Box::new), end.map(Box::new)).into()
+    }
+
+    /// Constructs a range expression, which includes `end` only if `inclusive`.
+    pub fn range(start: Expr, end: Expr, inclusive: bool, step: Option<Expr>) -> Self {
+        ExprKind::Range(
+            Box::new(start),
+            Box::new(end),
+            inclusive,
+            step.map(Box::new),
+        )
+        .into()
+    }
+
+    /// Constructs a record expression. Keys need not be valid identifiers.
+    pub fn record(fields: Vec<(&str, Expr)>) -> Self {
+        let fields = fields
+            .into_iter()
+            .map(|(key, value)| (key_identifier(key), value))
+            .collect();
+        ExprKind::Record(fields).into()
+    }
+
+    /// Constructs a field access expression. The field name need not be a valid identifier.
+    pub fn field(target: Expr, name: &str) -> Self {
+        ExprKind::Field(Box::new(target), key_identifier(name)).into()
+    }
+
+    /// Constructs a null-safe field access expression.
+    pub fn safe_field(target: Expr, name: &str) -> Self {
+        ExprKind::SafeField(Box::new(target), key_identifier(name)).into()
+    }
+
+    /// Constructs a conditional expression.
+    pub fn if_else(cond: Expr, then: Expr, otherwise: Expr) -> Self {
+        ExprKind::If(Box::new(cond), Box::new(then), Box::new(otherwise)).into()
+    }
+
+    /// Constructs an interpolated string expression.
+    pub fn interpolation(parts: Vec<StringPart>) -> Self {
+        ExprKind::Interpolation(parts).into()
+    }
+
+    /// Constructs a match expression.
+    pub fn match_arms(value: Expr, arms: Vec<(Pattern, Expr)>) -> Self {
+        ExprKind::Match(Box::new(value), arms).into()
+    }
+
+    /// Returns this expression located at `span`.
+    pub fn with_span(self, span: Span) -> Self {
+        Expr { span, ..self }
+    }
+
+    /// Returns this expression with `trivia` attached.
+    pub fn with_trivia(self, trivia: Trivia) -> Self {
+        Expr { trivia, ..self }
+    }
+}
+
+impl ExprKind {
+    /// Calls `f` on each direct subexpression, in source order.
+    pub fn for_each_child_mut<'a>(&'a mut self, mut f: impl FnMut(&'a mut Expr)) {
+        match self {
+            ExprKind::Literal(_) | ExprKind::Ident(_) => {}
+            ExprKind::UnaryExpr(_, e) | ExprKind::Parenthesized(e) | ExprKind::Lambda(_, e) => f(e),
+            ExprKind::BinaryExpr(l, _, r) | ExprKind::Index(l, r) => {
+                f(l);
+                f(r);
+            }
+            ExprKind::FunctionCall(_, args, named) => {
+                args.iter_mut().for_each(&mut f);
+                named.iter_mut().for_each(|(_, e)| f(e));
+            }
+            ExprKind::Call(callee, args, named) => {
+                f(callee);
+                args.iter_mut().for_each(&mut f);
+                named.iter_mut().for_each(|(_, e)| f(e));
+            }
+            ExprKind::List(items) => items.iter_mut().for_each(f),
+            ExprKind::Record(fields) => fields.iter_mut().for_each(|(_, e)| f(e)),
+            ExprKind::Field(target, _) | ExprKind::SafeField(target, _) => f(target),
+            ExprKind::If(cond, then, otherwise) => {
+                f(cond);
+                f(then);
+                f(otherwise);
+            }
+            ExprKind::Interpolation(parts) => {
+                for part in parts {
+                    if let StringPart::Expr(e) = part {
+                        f(e);
+                    }
+                }
+            }
+            ExprKind::Match(value, arms) => {
+                f(value);
+                arms.iter_mut().for_each(|(_, e)| f(e));
+            }
+            ExprKind::Slice(target, start, end) => {
+                f(target);
+                start.iter_mut().chain(end).for_each(|e| f(e));
+            }
+            ExprKind::Range(start, end, _, step) => {
+                f(start);
+                f(end);
+                step.iter_mut().for_each(|e| f(e));
+            }
+        }
+    }
+}
+
+/// Record keys and field names may be quoted, so any text is allowed.
+fn key_identifier(name: &str) -> Identifier {
+    Identifier {
+        name: name.to_string(),
+        span: Span::default(),
+    }
+}
+
+/// An expression with no source location, as built by hand rather than parsed.
+impl From<ExprKind> for Expr {
+    fn from(kind: ExprKind) -> Self {
+        Expr::new(kind, Span::default())
     }
 }
@@ -1,12 +1,41 @@
+use unicode_ident::is_xid_continue;
+use unicode_ident::is_xid_start;
+
+use super::span::Span;
 use crate::Error;
 
 /// An identifier in the language (e.g. variable name, function name).
-#[derive(Clone, Debug, PartialEq)]
-pub struct Identifier(pub String);
+#[derive(Clone, Debug)]
+pub struct Identifier {
+    pub name: String,
+    pub span: Span,
+}
+
+/// Identifiers are equal when they have the same name, wherever they appear.
+impl PartialEq for Identifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
 
 impl Identifier {
     pub fn as_str(&self) -> &str {
-        &self.0
+        &self.name
+    }
+
+    /// Whether `name` can be written bare: words joined by single hyphens, each starting with an
+    /// `XID_Start` character or `_` and continuing with `XID_Continue` characters. Any other
+    /// name must be backtick-quoted, as in `` `order id` ``.
+    pub fn is_valid(name: &str) -> bool {
+        name.split('-').all(|word| {
+            let mut chars = word.chars();
+            chars.next().is_some_and(|c| c == '_' || is_xid_start(c)) && chars.all(is_xid_continue)
+        })
+    }
+
+    /// Returns this identifier located at `span`.
+    pub fn with_span(self, span: Span) -> Self {
+        Identifier { span, ..self }
     }
 }
 
@@ -14,13 +43,29 @@ impl TryFrom<&str> for Identifier {
     type Error = crate::Error;
 
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        if !s.is_empty()
-            && s.chars()
-                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-        {
-            return Ok(Identifier(s.to_string()));
+        if Identifier::is_valid(s) {
+            return Ok(Identifier {
+                name: s.to_string(),
+                span: Span::default(),
+            });
         }
 
         Err(Error::SyntaxError("Invalid identifier".to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid() {
+        for name in ["x", "order-id", "a_b-c2", "_", "größe", "数量-a2"] {
+            assert!(Identifier::is_valid(name), "{name}");
+        }
+        for name in ["", "-a", "a-", "a--b", "x-1", "a b", "a.b", "2nd", "·a"] {
+            assert!(!Identifier::is_valid(name), "{name}");
+        }
+        assert!(Identifier::try_from("x-1").is_err());
+    }
+}
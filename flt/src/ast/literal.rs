@@ -2,17 +2,32 @@ use std::convert::TryFrom;
 
 use bigdecimal::BigDecimal;
 use bigdecimal::FromPrimitive;
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::TimeDelta;
+use chrono::Utc;
 
 use super::number::Numeric;
+use super::regex::Regex;
 use crate::errors::Error;
 
-/// A literal value: number, string, boolean, or symbol.
+/// A literal value: number, string, boolean, symbol, date, timestamp, duration, regex, or `null`.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
+    /// A missing value.
+    Null,
     Number(Numeric),
     String(String),
     Boolean(bool),
     Symbol(String),
+    /// A calendar date: `@2024-01-31`.
+    Date(NaiveDate),
+    /// An instant in UTC: `@2024-01-31T12:00:00Z`.
+    Timestamp(DateTime<Utc>),
+    /// A length of time: `90s`, `3d`, `1h30m`.
+    Duration(TimeDelta),
+    /// A regular expression: `~r"^[a-z]+$"`.
+    Regex(Regex),
 }
 
 impl Literal {
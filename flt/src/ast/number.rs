@@ -1,6 +1,9 @@
+use std::fmt;
+
 use bigdecimal::BigDecimal;
 
-/// A numeric literal: optional `+` or `-`, digits, then optionally `.` followed by any number of decimal digits.
+/// A numeric literal: an exact decimal, written in decimal (`1_000`, `2.5e-3`) or as an integer in
+/// hexadecimal, binary or octal (`0xFF`, `0b1010`, `0o17`).
 #[derive(Clone, Debug, PartialEq)]
 pub struct Numeric {
     value: BigDecimal,
@@ -25,3 +28,52 @@ impl AsRef<BigDecimal> for Numeric {
         &self.value
     }
 }
+
+/// The most zeros a number is padded with in plain notation. Beyond them it prints in
+/// scientific notation instead.
+const MAX_PADDING: u64 = 20;
+
+/// Formats the value in plain notation without trailing fractional zeros (`42.50` prints as
+/// `42.5`), or in scientific notation if that would take more than [`MAX_PADDING`] zeros
+/// (`1e25`, `1.5e-30`).
+impl fmt::Display for Numeric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.value.normalized();
+        let scale = value.fractional_digit_count();
+        // Zeros after the digits of an integer, or between the point and the digits of a fraction.
+        let padding = match u64::try_from(scale) {
+            Ok(scale) => scale.saturating_sub(value.digits()),
+            Err(_) => scale.unsigned_abs(),
+        };
+        if padding > MAX_PADDING {
+            write!(f, "{}", value.to_scientific_notation())
+        } else {
+            write!(f, "{}", value.to_plain_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn display(s: &str) -> String {
+        Numeric::new(BigDecimal::from_str(s).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(display("42.50"), "42.5");
+        assert_eq!(display("1e20"), "100000000000000000000");
+        assert_eq!(
+            display("0.000000000000000000001"),
+            "0.000000000000000000001"
+        );
+        assert_eq!(display("1e21"), "1e21");
+        assert_eq!(display("-1.5e-30"), "-1.5e-30");
+        assert_eq!(display("1e10000"), "1e10000");
+        assert_eq!(display("123456789e30"), "1.23456789e38");
+    }
+}
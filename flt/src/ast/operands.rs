@@ -6,19 +6,39 @@ pub enum UnaryOp {
     Minus,
 }
 
-/// Binary operand: `+`, `-`, `*`, `/`, `&`, `&&`, `|`, `||`, `^`, `^^`, `|>`.
+/// Binary operand: `+`, `-`, `*`, `/`, `//`, `%`, `**`, `&`, `&&`, `|`, `||`, `^`, `^^`, `==`, `!=`, `=~`, `!~`, `<`, `<=`, `>`, `>=`, `in`, `??`, `|>`.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum BinaryOp {
     Add,
     Sub,
     Mul,
     Div,
+    /// Floored integer division.
+    IntDiv,
+    /// Remainder of floored division: has the sign of the divisor.
+    Rem,
+    /// Right-associative exponentiation.
+    Pow,
     BitAnd,
     And,
     BitOr,
     Or,
     BitXor,
     Xor,
+    Eq,
+    Ne,
+    /// Regex match: whether the regex on the right matches the string on the left.
+    Match,
+    /// Negated regex match.
+    NotMatch,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Membership: whether the left operand is in the range, list, string or record on the right.
+    In,
+    /// Null coalescing: the left operand unless it is null, else the right one.
+    Coalesce,
     /// Elixir-style pipe: passes left as first argument to right.
     Pipe,
 }
@@ -0,0 +1,76 @@
+use super::identifier::Identifier;
+use super::literal::Literal;
+use super::span::Span;
+
+/// A pattern in a `match` arm.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// `_`: matches anything.
+    Wildcard,
+    /// A literal: matches an equal value, except that a regex matches the strings it matches.
+    Literal(Literal),
+    /// A name: matches anything and binds it.
+    Binding(Identifier),
+    /// A list: `[a, b]` matches a list of exactly that length; `[a, ..rest]` matches a list of
+    /// at least that length, and the rest pattern (a binding or `_`) receives the remaining items.
+    List(Vec<Pattern>, Option<Box<Pattern>>),
+    /// A record: `{name: n, age}` matches a record having at least those fields.
+    Record(Vec<(Identifier, Pattern)>),
+}
+
+impl Pattern {
+    /// Constructs a binding pattern.
+    pub fn binding(name: &str) -> Self {
+        Pattern::Binding(Identifier::try_from(name).expect("failed to convert identifier"))
+    }
+
+    /// Constructs a record pattern. Keys need not be valid identifiers.
+    pub fn record(fields: Vec<(&str, Pattern)>) -> Self {
+        let fields = fields
+            .into_iter()
+            .map(|(key, pattern)| {
+                let key = Identifier {
+                    name: key.to_string(),
+                    span: Span::default(),
+                };
+                (key, pattern)
+            })
+            .collect();
+        Pattern::Record(fields)
+    }
+
+    /// The names this pattern binds, in source order.
+    pub fn bindings(&self) -> Vec<&Identifier> {
+        match self {
+            Pattern::Wildcard | Pattern::Literal(_) => vec![],
+            Pattern::Binding(name) => vec![name],
+            Pattern::List(items, rest) => items
+                .iter()
+                .chain(rest.as_deref())
+                .flat_map(Pattern::bindings)
+                .collect(),
+            Pattern::Record(fields) => fields.iter().flat_map(|(_, p)| p.bindings()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bindings() {
+        let pattern = Pattern::List(
+            vec![
+                Pattern::record(vec![
+                    ("name", Pattern::binding("n")),
+                    ("tag", Pattern::Wildcard),
+                ]),
+                Pattern::Literal(Literal::symbol("ok")),
+            ],
+            Some(Box::new(Pattern::binding("rest"))),
+        );
+        let names: Vec<_> = pattern.bindings().iter().map(|i| i.as_str()).collect();
+        assert_eq!(names, vec!["n", "rest"]);
+    }
+}
@@ -0,0 +1,14 @@
+use super::statement::Statement;
+
+/// A program: statements separated by newlines or `;`, evaluated in order.
+/// Its value is the value of its last statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    pub statements: Vec<Statement>,
+}
+
+impl Program {
+    pub fn new(statements: Vec<Statement>) -> Self {
+        Self { statements }
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+
+use regex::RegexBuilder;
+
+use crate::Error;
+
+/// A regular expression literal: `~r"^[a-z]+$"`, compiled when it is parsed.
+/// Flags after the closing quote change how it matches: `i` (case-insensitive), `m` (`^` and `$`
+/// match at line breaks), `s` (`.` matches a line break) and `x` (whitespace and `#` comments
+/// in the pattern are ignored).
+#[derive(Clone, Debug)]
+pub struct Regex {
+    regex: regex::Regex,
+    flags: String,
+}
+
+impl Regex {
+    /// Compiles `pattern` with `flags`. An invalid pattern or unknown flag is a syntax error.
+    pub fn new(pattern: &str, flags: &str) -> Result<Self, Error> {
+        let mut builder = RegexBuilder::new(pattern);
+        for flag in flags.chars() {
+            match flag {
+                'i' => builder.case_insensitive(true),
+                'm' => builder.multi_line(true),
+                's' => builder.dot_matches_new_line(true),
+                'x' => builder.ignore_whitespace(true),
+                _ => return Err(Error::SyntaxError(format!("Unknown regex flag '{}'", flag))),
+            };
+        }
+        let regex = builder
+            .build()
+            .map_err(|e| Error::SyntaxError(e.to_string()))?;
+        Ok(Regex {
+            regex,
+            flags: flags.to_string(),
+        })
+    }
+
+    /// The pattern, as written.
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    pub fn flags(&self) -> &str {
+        &self.flags
+    }
+
+    /// Whether the pattern matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+/// Regexes are equal when written the same way.
+impl PartialEq for Regex {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str() && self.flags == other.flags
+    }
+}
+
+/// Formats the regex as a literal, with as many `#`s as its pattern needs.
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut hashes = String::new();
+        while self.as_str().contains(&format!("\"{}", hashes)) {
+            hashes.push('#');
+        }
+        write!(f, "~r{hashes}\"{}\"{hashes}{}", self.as_str(), self.flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex() {
+        let regex = Regex::new("^err(or)?$", "i").unwrap();
+        assert!(regex.is_match("ERROR"));
+        assert!(!regex.is_match("errors"));
+        assert_eq!(regex, Regex::new("^err(or)?$", "i").unwrap());
+        assert_ne!(regex, Regex::new("^err(or)?$", "").unwrap());
+        assert!(matches!(Regex::new("(", ""), Err(Error::SyntaxError(_))));
+        assert!(matches!(Regex::new("a", "q"), Err(Error::SyntaxError(_))));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Regex::new(r"\d+", "").unwrap().to_string(), r#"~r"\d+""#);
+        assert_eq!(
+            Regex::new(r#"say "hi""#, "im").unwrap().to_string(),
+            r##"~r#"say "hi""#im"##
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use super::expr::Expr;
+use super::identifier::Identifier;
+use super::span::Span;
+use super::trivia::Trivia;
+
+/// A statement in a program: what kind of statement it is, where it appears in the source, and
+/// the comments on the lines around it.
+#[derive(Clone, Debug)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+    pub trivia: Trivia,
+}
+
+/// Statements are equal when they have the same kind and trivia, wherever they appear.
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.trivia == other.trivia
+    }
+}
+
+/// The kinds of statement in the language.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatementKind {
+    /// A binding: `let name = expr`. Later statements refer to it as `Expr::Ident(name)`.
+    Let(Identifier, Expr),
+    /// A function definition: `def name(params) = expr`. The body may call `name` itself.
+    Def(Identifier, Vec<Identifier>, Expr),
+    /// An expression evaluated for its value.
+    Expr(Expr),
+}
+
+impl Statement {
+    /// Constructs a statement of the given kind located at `span`.
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Statement {
+            kind,
+            span,
+            trivia: Trivia::default(),
+        }
+    }
+
+    /// Returns this statement with `trivia` attached.
+    pub fn with_trivia(self, trivia: Trivia) -> Self {
+        Statement { trivia, ..self }
+    }
+
+    /// Constructs a `let` binding.
+    pub fn let_binding(name: impl TryInto<Identifier, Error = crate::Error>, value: Expr) -> Self {
+        StatementKind::Let(
+            name.try_into().expect("failed to convert identifier"),
+            value,
+        )
+        .into()
+    }
+
+    /// Constructs a function definition.
+    pub fn def(
+        name: impl TryInto<Identifier, Error = crate::Error>,
+        params: Vec<&str>,
+        body: Expr,
+    ) -> Self {
+        let params = params
+            .into_iter()
+            .map(|p| Identifier::try_from(p).expect("failed to convert identifier"))
+            .collect();
+        StatementKind::Def(
+            name.try_into().expect("failed to convert identifier"),
+            params,
+            body,
+        )
+        .into()
+    }
+
+    /// Constructs an expression statement.
+    pub fn expr(expr: Expr) -> Self {
+        StatementKind::Expr(expr).into()
+    }
+
+    /// The expression this statement holds: the bound value of a `let`, or the body of a `def`.
+    pub fn value(&self) -> &Expr {
+        match &self.kind {
+            StatementKind::Let(_, value)
+            | StatementKind::Def(_, _, value)
+            | StatementKind::Expr(value) => value,
+        }
+    }
+
+    /// The expression this statement holds, mutably.
+    pub fn value_mut(&mut self) -> &mut Expr {
+        match &mut self.kind {
+            StatementKind::Let(_, value)
+            | StatementKind::Def(_, _, value)
+            | StatementKind::Expr(value) => value,
+        }
+    }
+}
+
+/// A statement with no source location, as built by hand rather than parsed.
+impl From<StatementKind> for Statement {
+    fn from(kind: StatementKind) -> Self {
+        Statement::new(kind, Span::default())
+    }
+}
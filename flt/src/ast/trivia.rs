@@ -0,0 +1,41 @@
+use super::span::Span;
+
+/// A `#` line comment.
+#[derive(Clone, Debug)]
+pub struct Comment {
+    /// The text after the `#`, up to but not including the line break.
+    pub text: String,
+    pub span: Span,
+}
+
+/// Comments are equal when they have the same text, wherever they appear.
+impl PartialEq for Comment {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+impl Comment {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            span: Span::default(),
+        }
+    }
+}
+
+/// Comments attached to an AST node so that tools printing source back can keep them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trivia {
+    /// Comments on the lines before the node.
+    pub leading: Vec<Comment>,
+    /// Comments after the node, starting on the line where it ends. The last node of a
+    /// program or expression also takes the comments after everything else, on any line.
+    pub trailing: Vec<Comment>,
+}
+
+impl Trivia {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_empty()
+    }
+}
@@ -14,7 +14,7 @@ pub enum Error {
     #[error("Syntax Error: {0}")]
     SyntaxError(String),
     #[error("Runtime Error: {0}")]
-    RuntimeError(RuntimeError),
+    RuntimeError(#[from] RuntimeError),
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -25,4 +25,38 @@ pub enum RuntimeError {
     CannotCompare(String, String),
     #[error("Division By Zero")]
     DivisionByZero,
+    #[error("Undefined variable {0}")]
+    UndefinedVariable(String),
+    #[error("Undefined variable {0}; to subtract, write {1}")]
+    AmbiguousIdentifier(String, String),
+    #[error("Undefined function {0}")]
+    UndefinedFunction(String),
+    #[error("{0} is not a function (got {1})")]
+    NotAFunction(String, String),
+    #[error("{0} expects {1} argument(s) but got {2}")]
+    ArityMismatch(String, usize, usize),
+    #[error("{0} has no argument named {1}")]
+    UnknownArgument(String, String),
+    #[error("{0} got argument {1} more than once")]
+    DuplicateArgument(String, String),
+    #[error("Program has no statements")]
+    EmptyProgram,
+    #[error("Maximum call depth exceeded in {0}")]
+    CallDepthExceeded(String),
+    #[error("Index {0} out of range for length {1}")]
+    IndexOutOfRange(String, usize),
+    #[error("Slice start {0} is after its end {1}")]
+    InvalidSlice(usize, usize),
+    #[error("No field named {0}")]
+    UnknownField(String),
+    #[error("No match arm matches {0}")]
+    NonExhaustiveMatch(String),
+    #[error("Date or duration out of range")]
+    TimeOutOfRange,
+    #[error("Range step cannot be zero")]
+    ZeroStep,
+    #[error("Number out of range")]
+    NumberOutOfRange,
+    #[error("Result of {0} is not a real number")]
+    NotARealNumber(String),
 }
@@ -0,0 +1,1706 @@
+//! The flt tree-walking evaluator
+
+mod builtins;
+mod closure;
+mod env;
+mod range;
+mod record;
+mod time;
+mod value;
+
+use std::cmp::Ordering;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::num_bigint::ToBigInt;
+use bigdecimal::num_traits::Pow;
+use bigdecimal::BigDecimal;
+use bigdecimal::Signed;
+use bigdecimal::ToPrimitive;
+use bigdecimal::Zero;
+
+pub use closure::Closure;
+pub use env::Arguments;
+pub use env::Builtin;
+pub use env::Environment;
+pub use range::Range;
+pub use record::Record;
+pub use value::Value;
+
+use closure::Defs;
+
+use crate::ast::BinaryOp;
+use crate::ast::Expr;
+use crate::ast::ExprKind;
+use crate::ast::Identifier;
+use crate::ast::Literal;
+use crate::ast::Numeric;
+use crate::ast::Pattern;
+use crate::ast::Program;
+use crate::ast::StatementKind;
+use crate::ast::StringPart;
+use crate::ast::UnaryOp;
+use crate::errors::RuntimeError;
+use crate::Error;
+
+/// The stack evaluating an expression may use before evaluating the next one nested in it, with
+/// room to spare. When less than this is left, evaluation continues on a newly allocated stack
+/// of [`STACK_SIZE`] bytes, so deep recursion is bounded by the call depth rather than the stack.
+const RED_ZONE: usize = 128 * 1024;
+const STACK_SIZE: usize = 1024 * 1024;
+
+/// Evaluates `expr` against `env`, returning the resulting value.
+pub fn eval(expr: &Expr, env: &Environment) -> Result<Value, Error> {
+    stacker::maybe_grow(RED_ZONE, STACK_SIZE, || eval_expr(expr, env))
+}
+
+fn eval_expr(expr: &Expr, env: &Environment) -> Result<Value, Error> {
+    match &expr.kind {
+        ExprKind::Literal(literal) => Ok(Value::from(literal.clone())),
+        ExprKind::Ident(name) => env
+            .get(name)
+            .cloned()
+            .ok_or_else(|| undefined_variable(name, env).into()),
+        ExprKind::UnaryExpr(op, operand) => eval_unary(*op, eval(operand, env)?),
+        ExprKind::BinaryExpr(left, op, right) => eval_binary(left, *op, right, env),
+        ExprKind::FunctionCall(name, args, named) => {
+            let args = eval_arguments(None, args, named, env)?;
+            call(name.as_str(), &args, env)
+        }
+        ExprKind::Parenthesized(inner) => eval(inner, env),
+        ExprKind::Lambda(params, body) => Ok(Value::Function(Rc::new(Closure::new(
+            names(params),
+            (**body).clone(),
+            env.clone(),
+        )))),
+        ExprKind::List(items) => Ok(Value::List(
+            items
+                .iter()
+                .map(|item| eval(item, env))
+                .collect::<Result<_, _>>()?,
+        )),
+        ExprKind::Record(fields) => Ok(Value::Record(
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.as_str(), eval(value, env)?)))
+                .collect::<Result<_, Error>>()?,
+        )),
+        ExprKind::Field(..)
+        | ExprKind::SafeField(..)
+        | ExprKind::Index(..)
+        | ExprKind::Slice(..)
+        | ExprKind::Call(..) => Ok(eval_chain(expr, env)?.unwrap_or(Value::Null)),
+        ExprKind::Range(start, end, inclusive, step) => {
+            eval_range(start, end, *inclusive, step.as_deref(), env)
+        }
+        ExprKind::If(cond, then, otherwise) => {
+            if expect_boolean(eval(cond, env)?)? {
+                eval(then, env)
+            } else {
+                eval(otherwise, env)
+            }
+        }
+        ExprKind::Interpolation(parts) => eval_interpolation(parts, env),
+        ExprKind::Match(value, arms) => eval_match(eval(value, env)?, arms, env),
+    }
+}
+
+/// Evaluates the statements of `program` in order against `env` extended with its `let` bindings
+/// and `def` functions, returning the value of the last statement (for a `let`, the bound value;
+/// for a `def`, the function).
+/// A binding shadows any variable of the same name, and a function shadows any builtin of the
+/// same name; `env` itself is left unchanged.
+pub fn eval_program(program: &Program, env: &Environment) -> Result<Value, Error> {
+    let mut env = env.clone();
+    let defs = Rc::new(Defs::default());
+    let mut result = None;
+    for statement in &program.statements {
+        let value = match &statement.kind {
+            StatementKind::Let(name, value) => {
+                let value = eval(value, &env)?;
+                env.set(name.as_str(), value.clone());
+                value
+            }
+            StatementKind::Def(name, params, body) => {
+                let function = Value::Function(Rc::new(defs.define(
+                    name.as_str(),
+                    names(params),
+                    body.clone(),
+                    env.clone(),
+                )));
+                env.set(name.as_str(), function.clone());
+                function
+            }
+            StatementKind::Expr(expr) => eval(expr, &env)?,
+        };
+        result = Some(value);
+    }
+    result.ok_or_else(|| RuntimeError::EmptyProgram.into())
+}
+
+/// Evaluates an interpolated string, formatting the value of each hole as text.
+fn eval_interpolation(parts: &[StringPart], env: &Environment) -> Result<Value, Error> {
+    let mut text = String::new();
+    for part in parts {
+        match part {
+            StringPart::Text(s) => text.push_str(s),
+            StringPart::Expr(e) => text.push_str(&eval(e, env)?.to_text()),
+        }
+    }
+    Ok(Value::String(text))
+}
+
+/// Evaluates the first arm whose pattern matches `value`, with the pattern's bindings in scope.
+fn eval_match(value: Value, arms: &[(Pattern, Expr)], env: &Environment) -> Result<Value, Error> {
+    for (pattern, body) in arms {
+        let mut bindings = vec![];
+        if bind_pattern(pattern, &value, &mut bindings) {
+            let mut env = env.clone();
+            for (name, value) in bindings {
+                env.set(name, value);
+            }
+            return eval(body, &env);
+        }
+    }
+    Err(RuntimeError::NonExhaustiveMatch(value.to_string()).into())
+}
+
+/// Whether `value` matches `pattern`, collecting the values of the names it binds.
+fn bind_pattern(pattern: &Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> bool {
+    match (pattern, value) {
+        (Pattern::Wildcard, _) => true,
+        (Pattern::Literal(Literal::Regex(regex)), Value::String(s)) => regex.is_match(s),
+        (Pattern::Literal(literal), _) => Value::from(literal.clone()) == *value,
+        (Pattern::Binding(name), _) => {
+            bindings.push((name.as_str().to_string(), value.clone()));
+            true
+        }
+        (Pattern::List(patterns, rest), Value::List(items)) => {
+            let fits = match rest {
+                Some(_) => items.len() >= patterns.len(),
+                None => items.len() == patterns.len(),
+            };
+            fits && patterns
+                .iter()
+                .zip(items.iter())
+                .all(|(p, item)| bind_pattern(p, item, bindings))
+                && rest.as_ref().is_none_or(|rest| {
+                    bind_pattern(rest, &Value::List(items[patterns.len()..].into()), bindings)
+                })
+        }
+        (Pattern::Record(fields), Value::Record(record)) => fields.iter().all(|(key, p)| {
+            record
+                .get(key.as_str())
+                .is_some_and(|field| bind_pattern(p, field, bindings))
+        }),
+        _ => false,
+    }
+}
+
+/// A kebab-case name such as `a-b` is one identifier, so when it is undefined but its parts are
+/// all variables, the error suggests the subtraction that was probably meant.
+fn undefined_variable(name: &str, env: &Environment) -> RuntimeError {
+    let parts: Vec<&str> = name.split('-').collect();
+    if parts.len() > 1 && parts.iter().all(|part| env.get(part).is_some()) {
+        RuntimeError::AmbiguousIdentifier(name.to_string(), parts.join(" - "))
+    } else {
+        RuntimeError::UndefinedVariable(name.to_string())
+    }
+}
+
+fn names(identifiers: &[Identifier]) -> Vec<String> {
+    identifiers.iter().map(|i| i.as_str().to_string()).collect()
+}
+
+/// Evaluates the arguments of a call, in source order, after the piped value if there is one.
+fn eval_arguments(
+    piped: Option<Value>,
+    args: &[Expr],
+    named: &[(Identifier, Expr)],
+    env: &Environment,
+) -> Result<Arguments, Error> {
+    let positional = piped
+        .map(Ok)
+        .into_iter()
+        .chain(args.iter().map(|arg| eval(arg, env)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let named = named
+        .iter()
+        .map(|(name, arg)| Ok((name.as_str().to_string(), eval(arg, env)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(Arguments { positional, named })
+}
+
+/// Calls the function bound to the variable `name` if there is one, and the builtin `name` otherwise.
+/// Calling a variable that holds anything else is a `NotAFunction` error.
+fn call(name: &str, args: &Arguments, env: &Environment) -> Result<Value, Error> {
+    let variable = env.get(name);
+    if let Some(Value::Function(function)) = variable {
+        return function.call(name, args);
+    }
+    match (env.function(name), variable) {
+        (Some(function), _) => function(args),
+        (None, Some(value)) => Err(not_a_function(name, value)),
+        (None, None) => Err(RuntimeError::UndefinedFunction(name.to_string()).into()),
+    }
+}
+
+/// Evaluates a postfix chain: field, index, slice and call suffixes on a target, as in
+/// `rec?.items[0].name`. `rec?.name` is like `rec.name`, but when `rec` is null the rest of the
+/// chain is skipped, giving `None`, so the whole chain is null.
+fn eval_chain(expr: &Expr, env: &Environment) -> Result<Option<Value>, Error> {
+    let target =
+        |target: &Expr| stacker::maybe_grow(RED_ZONE, STACK_SIZE, || eval_chain(target, env));
+    let value = match &expr.kind {
+        ExprKind::SafeField(target_expr, name) => match target(target_expr)? {
+            None | Some(Value::Null) => return Ok(None),
+            Some(target) => field(target, name.as_str())?,
+        },
+        ExprKind::Field(target_expr, name) => match target(target_expr)? {
+            None => return Ok(None),
+            Some(target) => field(target, name.as_str())?,
+        },
+        ExprKind::Index(target_expr, index) => match target(target_expr)? {
+            None => return Ok(None),
+            Some(target) => eval_index(target, eval(index, env)?)?,
+        },
+        ExprKind::Slice(target_expr, start, end) => match target(target_expr)? {
+            None => return Ok(None),
+            Some(target) => eval_slice(target, start, end, env)?,
+        },
+        ExprKind::Call(callee, args, named) => match target(callee)? {
+            None => return Ok(None),
+            Some(callee) => eval_call(callee, None, args, named, env)?,
+        },
+        _ => eval(expr, env)?,
+    };
+    Ok(Some(value))
+}
+
+/// Calls `callee`, which must be a function, with the `piped` value, if any, before `args`.
+fn eval_call(
+    callee: Value,
+    piped: Option<Value>,
+    args: &[Expr],
+    named: &[(Identifier, Expr)],
+    env: &Environment,
+) -> Result<Value, Error> {
+    let function = expect_function(callee)?;
+    let args = eval_arguments(piped, args, named, env)?;
+    call_value(&function, &args)
+}
+
+/// Calls a function value, naming it in errors by its `def` name or its signature.
+fn call_value(function: &Rc<Closure>, args: &Arguments) -> Result<Value, Error> {
+    let name = function
+        .name()
+        .map_or_else(|| function.to_string(), str::to_string);
+    function.call(&name, args)
+}
+
+/// Unary operators propagate null: `-null` is `null`.
+fn eval_unary(op: UnaryOp, operand: Value) -> Result<Value, Error> {
+    match (op, operand) {
+        (_, Value::Null) => Ok(Value::Null),
+        (UnaryOp::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+        (UnaryOp::Plus, Value::Number(n)) => Ok(Value::Number(n)),
+        (UnaryOp::Minus, Value::Number(n)) => Ok(Value::Number(Numeric::new(-n.as_ref()))),
+        (UnaryOp::Plus, Value::Duration(d)) => Ok(Value::Duration(d)),
+        (UnaryOp::Minus, Value::Duration(d)) => Ok(Value::Duration(-d)),
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+/// `&&` and `||` follow three-valued logic, where null is an unknown boolean: `false && null` is
+/// `false` and `true || null` is `true`, but `true && null` and `false || null` are null.
+fn eval_binary(left: &Expr, op: BinaryOp, right: &Expr, env: &Environment) -> Result<Value, Error> {
+    match op {
+        BinaryOp::And | BinaryOp::Or => {
+            let decisive = op == BinaryOp::Or;
+            let left = expect_truth(eval(left, env)?)?;
+            // Short-circuit: the right operand is only evaluated when it decides the result.
+            if left == Some(decisive) {
+                return Ok(Value::Boolean(decisive));
+            }
+            Ok(match (left, expect_truth(eval(right, env)?)?) {
+                (_, Some(right)) if right == decisive => Value::Boolean(decisive),
+                (Some(_), Some(right)) => Value::Boolean(right),
+                _ => Value::Null,
+            })
+        }
+        BinaryOp::Coalesce => match eval(left, env)? {
+            Value::Null => eval(right, env),
+            left => Ok(left),
+        },
+        BinaryOp::Pipe => eval_pipe(eval(left, env)?, right, env),
+        _ => apply_binary(eval(left, env)?, op, eval(right, env)?),
+    }
+}
+
+/// `left |> right` calls `right` with `left` prepended to its arguments.
+/// `right` is either a call (`x |> f(y)` is `f(x, y)`), a bare function name (`x |> f` is `f(x)`),
+/// or any other expression evaluating to a function (`x |> fn(n) => n + 1`), which is called
+/// with `left` alone.
+/// A parenthesized pipeline on the right runs `left` through each of its stages in turn.
+fn eval_pipe(left: Value, right: &Expr, env: &Environment) -> Result<Value, Error> {
+    match &right.kind {
+        ExprKind::FunctionCall(name, args, named) => {
+            let args = eval_arguments(Some(left), args, named, env)?;
+            call(name.as_str(), &args, env)
+        }
+        ExprKind::Ident(name) => call(name, &Arguments::new([left]), env),
+        ExprKind::Parenthesized(inner) => eval_pipe(left, inner, env),
+        ExprKind::BinaryExpr(first, BinaryOp::Pipe, rest) => {
+            eval_pipe(eval_pipe(left, first, env)?, rest, env)
+        }
+        ExprKind::Call(callee, args, named) => {
+            eval_call(eval(callee, env)?, Some(left), args, named, env)
+        }
+        _ => call_value(
+            &expect_function(eval(right, env)?)?,
+            &Arguments::new([left]),
+        ),
+    }
+}
+
+/// Any operator but `==` and `!=` gives null when either operand is null. Equality treats null
+/// as a value, equal only to itself, so that `x == null` tests for it.
+fn apply_binary(left: Value, op: BinaryOp, right: Value) -> Result<Value, Error> {
+    use Value::Boolean;
+    use Value::Date;
+    use Value::Duration;
+    use Value::Number;
+    use Value::Timestamp;
+
+    let equality = matches!(op, BinaryOp::Eq | BinaryOp::Ne);
+    if !equality && (left == Value::Null || right == Value::Null) {
+        return Ok(Value::Null);
+    }
+    match (left, op, right) {
+        (Number(a), BinaryOp::Add, Number(b)) => Ok(number(a.as_ref() + b.as_ref())),
+        (Number(a), BinaryOp::Sub, Number(b)) => Ok(number(a.as_ref() - b.as_ref())),
+        (Number(a), BinaryOp::Mul, Number(b)) => Ok(number(a.as_ref() * b.as_ref())),
+        (Number(a), BinaryOp::Div, Number(b)) => {
+            if b.as_ref().is_zero() {
+                return Err(RuntimeError::DivisionByZero.into());
+            }
+            Ok(number(a.as_ref() / b.as_ref()))
+        }
+        (Number(a), BinaryOp::IntDiv, Number(b)) => Ok(number(floor_div_rem(&a, &b)?.0)),
+        (Number(a), BinaryOp::Rem, Number(b)) => Ok(number(floor_div_rem(&a, &b)?.1)),
+        (Number(a), BinaryOp::Pow, Number(b)) => power(&a, &b),
+        (Value::String(a), BinaryOp::Add, Value::String(b)) => Ok(Value::String(a + &b)),
+        (a @ (Date(_) | Timestamp(_)), BinaryOp::Add, Duration(d))
+        | (Duration(d), BinaryOp::Add, a @ (Date(_) | Timestamp(_))) => time::shift(a, d),
+        (a @ (Date(_) | Timestamp(_)), BinaryOp::Sub, Duration(d)) => time::shift(a, -d),
+        (a @ (Date(_) | Timestamp(_)), BinaryOp::Sub, b @ (Date(_) | Timestamp(_))) => {
+            time::since(&a, &b)
+        }
+        (Duration(a), BinaryOp::Add, Duration(b)) => a
+            .checked_add(&b)
+            .map(Duration)
+            .ok_or_else(|| RuntimeError::TimeOutOfRange.into()),
+        (Duration(a), BinaryOp::Sub, Duration(b)) => a
+            .checked_sub(&b)
+            .map(Duration)
+            .ok_or_else(|| RuntimeError::TimeOutOfRange.into()),
+        (Duration(d), BinaryOp::Mul, Number(n)) | (Number(n), BinaryOp::Mul, Duration(d)) => {
+            time::from_nanos(time::nanos(&d) * n.as_ref())
+        }
+        (Duration(d), BinaryOp::Div, Number(n)) => {
+            if n.as_ref().is_zero() {
+                return Err(RuntimeError::DivisionByZero.into());
+            }
+            time::from_nanos(time::nanos(&d) / n.as_ref())
+        }
+        (Duration(a), BinaryOp::Div, Duration(b)) => time::ratio(&a, &b),
+        (Number(a), BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor, Number(b)) => {
+            bitwise(&a, op, &b)
+        }
+        (Boolean(a), BinaryOp::BitAnd, Boolean(b)) => Ok(Boolean(a & b)),
+        (Boolean(a), BinaryOp::BitOr, Boolean(b)) => Ok(Boolean(a | b)),
+        (Boolean(a), BinaryOp::BitXor | BinaryOp::Xor, Boolean(b)) => Ok(Boolean(a ^ b)),
+        (Value::String(s), BinaryOp::Match, Value::Regex(r)) => Ok(Boolean(r.is_match(&s))),
+        (Value::String(s), BinaryOp::NotMatch, Value::Regex(r)) => Ok(Boolean(!r.is_match(&s))),
+        (item, BinaryOp::In, collection) => contains(&collection, &item).map(Boolean),
+        (a, BinaryOp::Eq, b) => Ok(Boolean(a == b)),
+        (a, BinaryOp::Ne, b) => Ok(Boolean(a != b)),
+        (a, BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge, b) => {
+            let ordering = compare(&a, &b)?;
+            Ok(Boolean(match op {
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }))
+        }
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+/// Orders two values of the same type: numbers numerically, strings and symbols by code point,
+/// `false` before `true`, durations by length, and dates and timestamps chronologically, where a
+/// date may be ordered against a timestamp as its midnight in UTC. Values of other different
+/// types cannot be ordered.
+fn compare(left: &Value, right: &Value) -> Result<Ordering, Error> {
+    if let Some(ordering) = time::compare(left, right) {
+        return Ok(ordering);
+    }
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => Ok(a.as_ref().cmp(b.as_ref())),
+        (Value::String(a), Value::String(b)) | (Value::Symbol(a), Value::Symbol(b)) => Ok(a.cmp(b)),
+        (Value::Boolean(a), Value::Boolean(b)) => Ok(a.cmp(b)),
+        (Value::Duration(a), Value::Duration(b)) => Ok(a.cmp(b)),
+        _ => Err(RuntimeError::CannotCompare(
+            left.type_name().to_string(),
+            right.type_name().to_string(),
+        )
+        .into()),
+    }
+}
+
+/// `item in collection`: whether an integer is in a range, a value is an item of a list, a string
+/// occurs in a string, or a string or symbol names a field of a record.
+fn contains(collection: &Value, item: &Value) -> Result<bool, Error> {
+    match (collection, item) {
+        (Value::Range(range), Value::Number(n)) => Ok(integer(n).is_ok_and(|n| range.contains(&n))),
+        (Value::List(items), item) => Ok(items.contains(item)),
+        (Value::String(s), Value::String(part)) => Ok(s.contains(part.as_str())),
+        (Value::Record(record), Value::String(key) | Value::Symbol(key)) => {
+            Ok(record.get(key).is_some())
+        }
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+/// A boolean, or `None` for null.
+fn expect_truth(value: Value) -> Result<Option<bool>, Error> {
+    match value {
+        Value::Null => Ok(None),
+        value => expect_boolean(value).map(Some),
+    }
+}
+
+fn expect_boolean(value: Value) -> Result<bool, Error> {
+    match value {
+        Value::Boolean(b) => Ok(b),
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+fn expect_function(value: Value) -> Result<Rc<Closure>, Error> {
+    match value {
+        Value::Function(function) => Ok(function),
+        value => Err(not_a_function(&value.to_string(), &value)),
+    }
+}
+
+fn not_a_function(name: &str, value: &Value) -> Error {
+    RuntimeError::NotAFunction(name.to_string(), value.type_name().to_string()).into()
+}
+
+fn number(value: BigDecimal) -> Value {
+    Value::Number(Numeric::new(value))
+}
+
+/// `rec.name`: the field `name` of a record.
+fn field(target: Value, name: &str) -> Result<Value, Error> {
+    match target {
+        Value::Record(record) => record
+            .get(name)
+            .cloned()
+            .ok_or_else(|| RuntimeError::UnknownField(name.to_string()).into()),
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+/// `xs[i]`: the item of a list or character of a string at `i`, counting from the end if negative.
+/// `rec[key]`: the field of a record named by a string or symbol.
+/// `xs[range]`: the items or characters at each position in the range, in its order.
+fn eval_index(target: Value, index: Value) -> Result<Value, Error> {
+    match (target, index) {
+        (target @ Value::Record(_), Value::String(key) | Value::Symbol(key)) => field(target, &key),
+        (target, Value::Range(range)) => eval_positions(target, &range),
+        (target, index) => eval_position(target, index),
+    }
+}
+
+fn eval_position(target: Value, index: Value) -> Result<Value, Error> {
+    match target {
+        Value::List(items) => {
+            let i = position(&index, items.len(), false)?;
+            Ok(items[i].clone())
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let i = position(&index, chars.len(), false)?;
+            Ok(Value::String(chars[i].to_string()))
+        }
+        Value::Range(range) => {
+            let Value::Number(n) = index else {
+                return Err(RuntimeError::InvalidOperandType.into());
+            };
+            let (i, len) = (integer(&n)?, range.len());
+            let resolved = if i.is_negative() {
+                &i + &len
+            } else {
+                i.clone()
+            };
+            range
+                .get(&resolved)
+                .map(|n| number(n.into()))
+                .ok_or_else(|| {
+                    RuntimeError::IndexOutOfRange(
+                        i.to_string(),
+                        len.to_usize().unwrap_or(usize::MAX),
+                    )
+                    .into()
+                })
+        }
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+fn eval_positions(target: Value, range: &Range) -> Result<Value, Error> {
+    match target {
+        Value::List(items) => Ok(Value::List(
+            range
+                .iter()
+                .map(|i| Ok(items[resolve(&i, items.len(), false)?].clone()))
+                .collect::<Result<_, Error>>()?,
+        )),
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            Ok(Value::String(
+                range
+                    .iter()
+                    .map(|i| Ok(chars[resolve(&i, chars.len(), false)?]))
+                    .collect::<Result<_, Error>>()?,
+            ))
+        }
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+/// `xs[a:b]`: the items of a list or characters of a string from `a` up to but not including `b`.
+/// Bounds count from the end if negative; a missing start is `0` and a missing end the length.
+fn eval_slice(
+    target: Value,
+    start: &Option<Box<Expr>>,
+    end: &Option<Box<Expr>>,
+    env: &Environment,
+) -> Result<Value, Error> {
+    let start = start.as_ref().map(|e| eval(e, env)).transpose()?;
+    let end = end.as_ref().map(|e| eval(e, env)).transpose()?;
+    let bounds = |len: usize| -> Result<(usize, usize), Error> {
+        let start = start.as_ref().map_or(Ok(0), |s| position(s, len, true))?;
+        let end = end.as_ref().map_or(Ok(len), |e| position(e, len, true))?;
+        if start > end {
+            return Err(RuntimeError::InvalidSlice(start, end).into());
+        }
+        Ok((start, end))
+    };
+    match target {
+        Value::List(items) => {
+            let (start, end) = bounds(items.len())?;
+            Ok(Value::List(items[start..end].into()))
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let (start, end) = bounds(chars.len())?;
+            Ok(Value::String(chars[start..end].iter().collect()))
+        }
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+/// `start..end step step`: bounds and step must be integers, and null in any of them gives null.
+fn eval_range(
+    start: &Expr,
+    end: &Expr,
+    inclusive: bool,
+    step: Option<&Expr>,
+    env: &Environment,
+) -> Result<Value, Error> {
+    let bound = |e: &Expr| match eval(e, env)? {
+        Value::Null => Ok(None),
+        Value::Number(n) => integer(&n).map(Some),
+        _ => Err(Error::from(RuntimeError::InvalidOperandType)),
+    };
+    let (start, end) = (bound(start)?, bound(end)?);
+    let step = step.map_or(Ok(Some(BigInt::from(1))), bound)?;
+    match (start, end, step) {
+        (Some(start), Some(end), Some(step)) => {
+            Ok(Value::from(Range::new(start, end, inclusive, step)?))
+        }
+        _ => Ok(Value::Null),
+    }
+}
+
+/// Resolves `index`, an integer that counts from the end if negative, against a sequence of `len` items.
+/// A slice bound may also equal `len`.
+fn position(index: &Value, len: usize, bound: bool) -> Result<usize, Error> {
+    let Value::Number(n) = index else {
+        return Err(RuntimeError::InvalidOperandType.into());
+    };
+    resolve(&integer(n)?, len, bound)
+}
+
+fn resolve(i: &BigInt, len: usize, bound: bool) -> Result<usize, Error> {
+    let resolved = if i.is_negative() { i + len } else { i.clone() };
+    resolved
+        .to_usize()
+        .filter(|&r| r < len || (bound && r == len))
+        .ok_or_else(|| RuntimeError::IndexOutOfRange(i.to_string(), len).into())
+}
+
+/// Floored division: the quotient is rounded toward negative infinity and the remainder has the
+/// sign of the divisor, so that `a == (a // b) * b + a % b`. Both are exact.
+fn floor_div_rem(a: &Numeric, b: &Numeric) -> Result<(BigDecimal, BigDecimal), Error> {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    if b.is_zero() {
+        return Err(RuntimeError::DivisionByZero.into());
+    }
+    let mut rem = a % b;
+    if !rem.is_zero() && rem.is_negative() != b.is_negative() {
+        rem += b;
+    }
+    let quotient = ((a - &rem) / b).with_scale(0);
+    Ok((quotient, rem))
+}
+
+/// The most bits the digits of an exact power may take, about 100 000 decimal digits.
+const MAX_POWER_BITS: u64 = 332_200;
+/// The most decimal places, or trailing zeros, a power may have.
+const MAX_POWER_SCALE: i64 = 100_000;
+
+/// Raises `base` to `exponent`: exactly for non-negative integer exponents, to the default
+/// precision of `BigDecimal` for negative ones, and through `f64` for fractional ones, keeping
+/// the shortest decimal that converts back to the same `f64`. A result larger or more precise
+/// than the limits above is a `NumberOutOfRange` error, and one that is not real, such as
+/// `(-1) ** 0.5`, a `NotARealNumber` error.
+fn power(base: &Numeric, exponent: &Numeric) -> Result<Value, Error> {
+    let (base, exponent) = (base.as_ref(), exponent.as_ref());
+    let result = match exponent.to_i64().filter(|_| exponent.is_integer()) {
+        Some(n) if n >= 0 => {
+            let (digits, scale) = base.as_bigint_and_exponent();
+            let n = n.unsigned_abs();
+            let bits = digits.bits().saturating_sub(1).saturating_mul(n);
+            let scale = i64::try_from(n)
+                .ok()
+                .and_then(|n| scale.checked_mul(n))
+                .filter(|s| s.abs() <= MAX_POWER_SCALE);
+            match scale {
+                Some(scale) if bits <= MAX_POWER_BITS => {
+                    BigDecimal::new(Pow::pow(&digits, n), scale)
+                }
+                _ => return Err(RuntimeError::NumberOutOfRange.into()),
+            }
+        }
+        Some(_) if base.is_zero() => return Err(RuntimeError::DivisionByZero.into()),
+        Some(n) => base.powi(n),
+        None => {
+            let result = base
+                .to_f64()
+                .zip(exponent.to_f64())
+                .map(|(base, exponent)| base.powf(exponent))
+                .ok_or(RuntimeError::NumberOutOfRange)?;
+            if result.is_nan() {
+                return Err(RuntimeError::NotARealNumber("**".to_string()).into());
+            }
+            if result.is_infinite() && base.is_zero() {
+                return Err(RuntimeError::DivisionByZero.into());
+            }
+            if result.is_infinite() {
+                return Err(RuntimeError::NumberOutOfRange.into());
+            }
+            BigDecimal::from_str(&result.to_string()).map_err(|_| RuntimeError::NumberOutOfRange)?
+        }
+    };
+    if result.as_bigint_and_exponent().1.abs() > MAX_POWER_SCALE {
+        return Err(RuntimeError::NumberOutOfRange.into());
+    }
+    Ok(number(result))
+}
+
+/// Bitwise operators are only defined on numbers with no fractional part.
+fn integer(n: &Numeric) -> Result<BigInt, Error> {
+    Some(n.as_ref())
+        .filter(|n| n.is_integer())
+        .and_then(ToBigInt::to_bigint)
+        .ok_or_else(|| RuntimeError::InvalidOperandType.into())
+}
+
+fn bitwise(a: &Numeric, op: BinaryOp, b: &Numeric) -> Result<Value, Error> {
+    let (a, b) = (integer(a)?, integer(b)?);
+    let result = match op {
+        BinaryOp::BitAnd => a & b,
+        BinaryOp::BitOr => a | b,
+        _ => a ^ b,
+    };
+    Ok(number(BigDecimal::from(result)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use super::*;
+
+    /// Parses the whole of `input` as an expression and evaluates it against `env`.
+    fn run_in(input: &str, env: &Environment) -> Result<Value, Error> {
+        eval(&crate::parse(input).expect("parse should succeed"), env)
+    }
+
+    fn run(input: &str) -> Result<Value, Error> {
+        run_in(input, &Environment::new())
+    }
+
+    /// Parses the whole of `input` as a program and evaluates it against `env`.
+    fn run_program_in(input: &str, env: &Environment) -> Result<Value, Error> {
+        eval_program(
+            &crate::parse_program(input).expect("parse should succeed"),
+            env,
+        )
+    }
+
+    fn run_program(input: &str) -> Result<Value, Error> {
+        run_program_in(input, &Environment::new())
+    }
+
+    fn list(items: &[&str]) -> Value {
+        Value::List(items.iter().map(|i| n(i)).collect())
+    }
+
+    fn n(s: &str) -> Value {
+        Value::Number(Numeric::new(BigDecimal::from_str(s).unwrap()))
+    }
+
+    #[test]
+    fn test_eval_literals() {
+        assert_eq!(run("42").unwrap(), n("42"));
+        assert_eq!(run(r#""hello""#).unwrap(), Value::from("hello"));
+        assert_eq!(run("true").unwrap(), Value::from(true));
+        assert_eq!(run(":ok").unwrap(), Value::Symbol("ok".into()));
+        assert_eq!(run("null").unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_eval_arithmetic() {
+        assert_eq!(run("1 + 2 * 3").unwrap(), n("7"));
+        assert_eq!(run("(1 + 2) * 3").unwrap(), n("9"));
+        assert_eq!(run("0.1 + 0.2").unwrap(), n("0.3"));
+        assert_eq!(run("7 / 2").unwrap(), n("3.5"));
+        assert_eq!(run("-(2 - 5)").unwrap(), n("3"));
+    }
+
+    #[test]
+    fn test_eval_integer_division_and_remainder() {
+        assert_eq!(run("7 // 2").unwrap(), n("3"));
+        assert_eq!(run("7 % 2").unwrap(), n("1"));
+        // Floored: the remainder takes the sign of the divisor.
+        assert_eq!(run("-7 // 2").unwrap(), n("-4"));
+        assert_eq!(run("-7 % 2").unwrap(), n("1"));
+        assert_eq!(run("7 % -2").unwrap(), n("-1"));
+        assert_eq!(run("5.5 % 2").unwrap(), n("1.5"));
+        assert_eq!(run("0.3 // 0.1").unwrap(), n("3"));
+        assert_eq!(run("10 - 7 % 4 * 2").unwrap(), n("4"));
+        for input in ["1 % 0", "1 // 0"] {
+            assert!(matches!(
+                run(input),
+                Err(Error::RuntimeError(RuntimeError::DivisionByZero))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_eval_power() {
+        assert_eq!(run("2 ** 10").unwrap(), n("1024"));
+        assert_eq!(run("2 ** 3 ** 2").unwrap(), n("512"));
+        assert_eq!(run("-2 ** 2").unwrap(), n("-4"));
+        assert_eq!(run("(-2) ** 2").unwrap(), n("4"));
+        assert_eq!(run("2 ** -2").unwrap(), n("0.25"));
+        assert_eq!(run("1.1 ** 2").unwrap(), n("1.21"));
+        assert_eq!(run("100 * 1.05 ** 2").unwrap(), n("110.25"));
+        assert_eq!(
+            run("2 ** 100").unwrap(),
+            n("1267650600228229401496703205376")
+        );
+        assert_eq!(run("4 ** 0.5").unwrap(), n("2"));
+        assert!(matches!(
+            run("0 ** -1"),
+            Err(Error::RuntimeError(RuntimeError::DivisionByZero))
+        ));
+        assert_eq!(run("-1 ** 0.5").unwrap(), n("-1"));
+        assert!(matches!(
+            run("(-1) ** 0.5"),
+            Err(Error::RuntimeError(RuntimeError::NotARealNumber(op))) if op == "**"
+        ));
+        assert_eq!(
+            run("(-8) ** 0.5").unwrap_err().to_string(),
+            "Runtime Error: Result of ** is not a real number"
+        );
+        assert!(matches!(
+            run("0 ** -0.5"),
+            Err(Error::RuntimeError(RuntimeError::DivisionByZero))
+        ));
+        assert_eq!(run("2 ** 0.5").unwrap(), n("1.4142135623730951"));
+        assert_eq!(run("10 ** 0.3").unwrap(), n("1.9952623149688795"));
+        assert_eq!(run("1 ** 10000000000").unwrap(), n("1"));
+    }
+
+    #[test]
+    fn test_eval_power_out_of_range() {
+        for input in [
+            "2 ** 1000000",
+            "7 ** 4294967296",
+            "0.1 ** 200000",
+            "10 ** -200000",
+            "10 ** 400.5",
+        ] {
+            assert!(
+                matches!(
+                    run(input),
+                    Err(Error::RuntimeError(RuntimeError::NumberOutOfRange))
+                ),
+                "{input}"
+            );
+        }
+        assert!(run("2 ** 100000").is_ok());
+    }
+
+    #[test]
+    fn test_eval_division_by_zero() {
+        assert!(matches!(
+            run("1 / 0"),
+            Err(Error::RuntimeError(RuntimeError::DivisionByZero))
+        ));
+    }
+
+    #[test]
+    fn test_eval_string_concatenation() {
+        assert_eq!(run(r#""foo" + "bar""#).unwrap(), Value::from("foobar"));
+    }
+
+    #[test]
+    fn test_eval_logical() {
+        assert_eq!(run("!true").unwrap(), Value::from(false));
+        assert_eq!(run("true && false").unwrap(), Value::from(false));
+        assert_eq!(run("false || true").unwrap(), Value::from(true));
+        assert_eq!(run("true ^^ true").unwrap(), Value::from(false));
+        // The right operand is never evaluated, so the undefined variable is not an error.
+        assert_eq!(run("false && missing").unwrap(), Value::from(false));
+        assert_eq!(run("true || missing").unwrap(), Value::from(true));
+    }
+
+    #[test]
+    fn test_eval_null() {
+        for input in [
+            "null + 1", "2 * null", "-null", "!null", "null < 1", "1 & null",
+        ] {
+            assert_eq!(run(input).unwrap(), Value::Null, "{input}");
+        }
+        assert_eq!(run("null == null").unwrap(), Value::from(true));
+        assert_eq!(run("1 != null").unwrap(), Value::from(true));
+        assert_eq!(run("false && null").unwrap(), Value::from(false));
+        assert_eq!(run("null && false").unwrap(), Value::from(false));
+        assert_eq!(run("null || true").unwrap(), Value::from(true));
+        assert_eq!(run("true && null").unwrap(), Value::Null);
+        assert_eq!(run("null || false").unwrap(), Value::Null);
+        assert_eq!(run("null ^^ true").unwrap(), Value::Null);
+        assert!(run("null && 1").is_err());
+    }
+
+    #[test]
+    fn test_eval_null_coalescing() {
+        let mut env = Environment::new();
+        env.set("row", run("{price: null, qty: 2}").unwrap());
+        env.set("missing", Value::Null);
+        assert_eq!(run_in("row.price ?? 0", &env).unwrap(), n("0"));
+        assert_eq!(run_in("row.qty ?? 0", &env).unwrap(), n("2"));
+        assert_eq!(run_in("false ?? true", &env).unwrap(), Value::from(false));
+        assert_eq!(run_in("missing?.price ?? 1", &env).unwrap(), n("1"));
+        assert_eq!(run_in("row?.qty", &env).unwrap(), n("2"));
+        assert!(run_in("missing.price", &env).is_err());
+        // A `?.` that meets null skips the rest of the chain, but only the rest of it.
+        for input in ["missing?.a.b", "missing?.a[0].b(1)", "missing?.a[1:]"] {
+            assert_eq!(run_in(input, &env).unwrap(), Value::Null, "{input}");
+        }
+        assert!(run_in("(missing?.a).b", &env).is_err());
+        assert!(run_in("row?.price.b", &env).is_err());
+        // The right operand is only evaluated when the left one is null.
+        assert_eq!(run_in("row.qty ?? undefined", &env).unwrap(), n("2"));
+        assert_eq!(
+            run_in("match missing { null => :none, _ => :some }", &env).unwrap(),
+            Value::Symbol("none".into())
+        );
+    }
+
+    #[test]
+    fn test_eval_time_arithmetic() {
+        let show = |input: &str| run(input).unwrap().to_string();
+        assert_eq!(show("@2024-01-31T12:00:00Z + 90s"), "@2024-01-31T12:01:30Z");
+        assert_eq!(show("1h + @2024-01-31T23:30:00Z"), "@2024-02-01T00:30:00Z");
+        assert_eq!(show("@2024-03-01T00:00:00Z - 1d"), "@2024-02-29T00:00:00Z");
+        assert_eq!(show("@2024-01-31T12:00:00 + 1s"), "@2024-01-31T12:00:01Z");
+        assert_eq!(
+            run("@2024-01-31T12:00:00 == @2024-01-31T13:00:00+01:00").unwrap(),
+            Value::from(true)
+        );
+        assert_eq!(show("@2024-01-31 + 1d"), "@2024-02-01");
+        assert_eq!(show("@2024-01-31 - 1w"), "@2024-01-24");
+        assert_eq!(show("@2024-01-31 + 6h"), "@2024-01-31T06:00:00Z");
+        assert_eq!(
+            show("@2024-01-31T12:00:00Z - @2024-01-30T10:30:00Z"),
+            "1d1h30m"
+        );
+        assert_eq!(show("@2024-01-01 - @2024-01-31"), "-30d");
+        assert_eq!(show("1h - 90m"), "-30m");
+        assert_eq!(show("-(1m + 500ms)"), "-1m500ms");
+        assert_eq!(show("1h30m * 2"), "3h");
+        assert_eq!(show("1s / 4"), "250ms");
+        assert_eq!(show("0.5 * 1ms"), "500us");
+        assert_eq!(show("1d / 1h"), "24");
+        assert_eq!(show("1s - 1s"), "0s");
+        assert_eq!(show("null + 1d"), "null");
+        assert!(matches!(
+            run("1d / 0"),
+            Err(Error::RuntimeError(RuntimeError::DivisionByZero))
+        ));
+        assert!(matches!(
+            run("@2024-01-31 + 1"),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+        assert!(matches!(
+            run("@2024-01-31 + 100_000_000d"),
+            Err(Error::RuntimeError(RuntimeError::TimeOutOfRange))
+        ));
+    }
+
+    #[test]
+    fn test_eval_time_comparison() {
+        let mut env = Environment::new();
+        env.set("at", run("@2024-01-31T09:15:00Z").unwrap());
+        assert_eq!(
+            run_in("at >= @2024-01-01 && at < @2024-02-01", &env).unwrap(),
+            Value::from(true)
+        );
+        assert_eq!(
+            run_in("at > @2024-01-31T09:15:00Z - 1h", &env).unwrap(),
+            Value::from(true)
+        );
+        assert_eq!(run_in("90s == 1m30s", &env).unwrap(), Value::from(true));
+        assert_eq!(run_in("1d > 23h", &env).unwrap(), Value::from(true));
+        // A date equals a timestamp at its midnight in UTC, as it is ordered against one.
+        for input in [
+            "@2024-01-31 == @2024-01-31T00:00:00Z",
+            "@2024-01-31 <= @2024-01-31T00:00:00Z && @2024-01-31 >= @2024-01-31T00:00:00Z",
+            "@2024-01-31 != @2024-01-31T00:00:01Z",
+            "@2024-01-31T00:00:00Z in [@2024-01-31]",
+        ] {
+            assert_eq!(run_in(input, &env).unwrap(), Value::from(true), "{input}");
+        }
+        assert!(matches!(
+            run_in("1d < 1", &env),
+            Err(Error::RuntimeError(RuntimeError::CannotCompare(a, b)))
+                if a == "duration" && b == "number"
+        ));
+        assert_eq!(
+            run_in(r#""on ${@2024-01-31}, for ${1h30m}""#, &env).unwrap(),
+            Value::from("on 2024-01-31, for 1h30m")
+        );
+    }
+
+    #[test]
+    fn test_eval_bitwise() {
+        assert_eq!(run("12 & 10").unwrap(), n("8"));
+        assert_eq!(run("12 | 10").unwrap(), n("14"));
+        assert_eq!(run("12 ^ 10").unwrap(), n("6"));
+        assert_eq!(run("true & false").unwrap(), Value::from(false));
+        assert_eq!(run("0xF0 | 0b1111").unwrap(), n("255"));
+        assert_eq!(run("0xFF_FF ^ 0o777").unwrap(), n("65024"));
+        assert_eq!(run("1e3 & 0xFF").unwrap(), n("232"));
+        assert_eq!(run("-0x1 & 0xFF").unwrap(), n("255"));
+        assert!(matches!(
+            run("1.5 & 1"),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+    }
+
+    #[test]
+    fn test_eval_invalid_operand_type() {
+        assert!(matches!(
+            run(r#"1 + "a""#),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+        assert!(matches!(
+            run("!1"),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+    }
+
+    #[test]
+    fn test_eval_equality() {
+        assert_eq!(run("1 == 1.0").unwrap(), Value::from(true));
+        assert_eq!(run("1 != 2").unwrap(), Value::from(true));
+        assert_eq!(run(r#""a" == "a""#).unwrap(), Value::from(true));
+        assert_eq!(run(":ok == :ok").unwrap(), Value::from(true));
+        assert_eq!(run("true != false").unwrap(), Value::from(true));
+        // Values of different types are never equal.
+        assert_eq!(run(r#"1 == "1""#).unwrap(), Value::from(false));
+        assert_eq!(run(r#":ok != "ok""#).unwrap(), Value::from(true));
+    }
+
+    #[test]
+    fn test_eval_comparison() {
+        assert_eq!(run("1 < 2").unwrap(), Value::from(true));
+        assert_eq!(run("2.5 <= 2.50").unwrap(), Value::from(true));
+        assert_eq!(run("-1 > 1").unwrap(), Value::from(false));
+        assert_eq!(run("10 >= 9").unwrap(), Value::from(true));
+        assert_eq!(run(r#""apple" < "banana""#).unwrap(), Value::from(true));
+        assert_eq!(run(":b > :a").unwrap(), Value::from(true));
+        assert_eq!(run("false < true").unwrap(), Value::from(true));
+        assert_eq!(run("1 + 1 == 2 && 3 > 2").unwrap(), Value::from(true));
+        assert!(matches!(
+            run(r#"1 < "2""#),
+            Err(Error::RuntimeError(RuntimeError::CannotCompare(a, b))) if a == "number" && b == "string"
+        ));
+    }
+
+    #[test]
+    fn test_eval_identifier() {
+        let mut env = Environment::new();
+        env.set("x", 41);
+        assert_eq!(run_in("x + 1", &env).unwrap(), n("42"));
+        assert!(matches!(
+            run("y"),
+            Err(Error::RuntimeError(RuntimeError::UndefinedVariable(name))) if name == "y"
+        ));
+    }
+
+    #[test]
+    fn test_eval_kebab_case_identifier() {
+        let mut env = Environment::new();
+        env.set("total", 10);
+        env.set("fee", 3);
+        env.set("order-id", 7);
+        assert_eq!(run_in("order-id", &env).unwrap(), n("7"));
+        assert_eq!(run_in("total - fee", &env).unwrap(), n("7"));
+        assert_eq!(run_in("total-1", &env).unwrap(), n("9"));
+        let err = run_in("total-fee", &env).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Runtime Error: Undefined variable total-fee; to subtract, write total - fee"
+        );
+        assert!(matches!(
+            run_in("total-tax", &env),
+            Err(Error::RuntimeError(RuntimeError::UndefinedVariable(name))) if name == "total-tax"
+        ));
+    }
+
+    #[test]
+    fn test_eval_quoted_identifier() {
+        let mut env = Environment::new();
+        env.set("order id", 41);
+        env.set("größe", 2);
+        assert_eq!(run_in("`order id` + 1", &env).unwrap(), n("42"));
+        assert_eq!(run_in("größe * `größe`", &env).unwrap(), n("4"));
+    }
+
+    #[test]
+    fn test_eval_program() {
+        let mut env = Environment::new();
+        env.set("name", "  flt  ");
+        let program = crate::parse_program(
+            "let trimmed = name |> trim\nlet shout = trimmed |> upper; shout + \"!\"",
+        )
+        .unwrap();
+        assert_eq!(eval_program(&program, &env).unwrap(), Value::from("FLT!"));
+        assert_eq!(env.get("trimmed"), None);
+
+        let program = crate::parse_program("let x = 1; let x = x + 1").unwrap();
+        assert_eq!(eval_program(&program, &env).unwrap(), n("2"));
+        let program = crate::parse_program("let x = y; 1").unwrap();
+        assert!(matches!(
+            eval_program(&program, &env),
+            Err(Error::RuntimeError(RuntimeError::UndefinedVariable(name))) if name == "y"
+        ));
+        assert!(matches!(
+            eval_program(&Program::new(vec![]), &env),
+            Err(Error::RuntimeError(RuntimeError::EmptyProgram))
+        ));
+    }
+
+    #[test]
+    fn test_eval_lambda() {
+        assert_eq!(
+            run_program("let add = fn(x, y) => x + y; add(1, 2)").unwrap(),
+            n("3")
+        );
+        assert_eq!(
+            run_program("let shout = fn(s) => s |> trim |> upper\n\"  hi \" |> shout").unwrap(),
+            Value::from("HI")
+        );
+        assert_eq!(run("fn(x, y) => x").unwrap().to_string(), "fn(x, y)");
+        assert!(matches!(
+            run_program("let f = fn(x) => x; f(1, 2)"),
+            Err(Error::RuntimeError(RuntimeError::ArityMismatch(name, 1, 2))) if name == "f"
+        ));
+        assert!(matches!(
+            run_program("let f = fn(x) => x; f(1, y: 2)"),
+            Err(Error::RuntimeError(RuntimeError::UnknownArgument(name, arg)))
+                if name == "f" && arg == "y"
+        ));
+    }
+
+    #[test]
+    fn test_eval_closure_captures_environment() {
+        let program = crate::parse_program(
+            "let rate = 2\nlet scale = fn(x) => x * rate\nlet rate = 10\nscale(3) + rate",
+        )
+        .unwrap();
+        // `scale` keeps the `rate` it was created with.
+        assert_eq!(
+            eval_program(&program, &Environment::new()).unwrap(),
+            n("16")
+        );
+
+        let program = crate::parse_program(
+            "let adder = fn(n) => fn(x) => x + n\nlet add2 = adder(2)\nadd2(40)",
+        )
+        .unwrap();
+        assert_eq!(
+            eval_program(&program, &Environment::new()).unwrap(),
+            n("42")
+        );
+    }
+
+    #[test]
+    fn test_eval_def() {
+        let mut env = Environment::new();
+        env.set("name", "  Hello ");
+        let program = crate::parse_program("def clean(s) = trim(lower(s))\nname |> clean").unwrap();
+        assert_eq!(eval_program(&program, &env).unwrap(), Value::from("hello"));
+
+        let program = crate::parse_program("def clean(s) = trim(s); clean(1, 2)").unwrap();
+        assert!(matches!(
+            eval_program(&program, &env),
+            Err(Error::RuntimeError(RuntimeError::ArityMismatch(name, 1, 2))) if name == "clean"
+        ));
+        assert_eq!(
+            eval_program(&program, &env).unwrap_err().to_string(),
+            "Runtime Error: clean expects 1 argument(s) but got 2"
+        );
+    }
+
+    #[test]
+    fn test_eval_def_shadows_builtin() {
+        let program = crate::parse_program(
+            "let before = upper(\"a\")\ndef upper(s) = s + \"!\"\nbefore + upper(\"b\")",
+        )
+        .unwrap();
+        assert_eq!(
+            eval_program(&program, &Environment::new()).unwrap(),
+            Value::from("Ab!")
+        );
+    }
+
+    #[test]
+    fn test_eval_recursion() {
+        let program =
+            crate::parse_program("def even(n) = n == 0 || !even(n - 1)\neven(10) && !even(7)")
+                .unwrap();
+        assert_eq!(
+            eval_program(&program, &Environment::new()).unwrap(),
+            Value::from(true)
+        );
+
+        let program = crate::parse_program(
+            "def count(n) = if n == 0 then 0 else 1 + count(n - 1)\ncount(5000)",
+        )
+        .unwrap();
+        assert_eq!(
+            eval_program(&program, &Environment::new()).unwrap(),
+            n("5000")
+        );
+
+        let program = crate::parse_program("def forever(n) = forever(n + 1); forever(0)").unwrap();
+        assert!(matches!(
+            eval_program(&program, &Environment::new()),
+            Err(Error::RuntimeError(RuntimeError::CallDepthExceeded(name))) if name == "forever"
+        ));
+    }
+
+    #[test]
+    fn test_eval_mutual_recursion() {
+        let program = crate::parse_program(
+            "def even(n) = n == 0 || odd(n - 1)\ndef odd(n) = n != 0 && even(n - 1)\neven(10) && odd(7)",
+        )
+        .unwrap();
+        assert_eq!(
+            eval_program(&program, &Environment::new()).unwrap(),
+            Value::from(true)
+        );
+
+        // A def returned from its program still sees the defs after it.
+        let program =
+            crate::parse_program("def first() = second()\ndef second() = 2\nfirst").unwrap();
+        let Value::Function(first) = eval_program(&program, &Environment::new()).unwrap() else {
+            panic!("expected a function");
+        };
+        assert_eq!(first.call("first", &Arguments::default()).unwrap(), n("2"));
+
+        // A variable captured by a def hides a later def of the same name.
+        let program = crate::parse_program("let g = 5\ndef f() = g\ndef g() = 1\nf()").unwrap();
+        assert_eq!(eval_program(&program, &Environment::new()).unwrap(), n("5"));
+    }
+
+    #[test]
+    fn test_eval_named_arguments_of_user_functions() {
+        assert_eq!(
+            run_program("let f = fn(x, y) => x - y; f(y: 1, x: 3)").unwrap(),
+            n("2")
+        );
+        assert_eq!(
+            run_program("def div(a, b) = a / b; div(8, b: 2)").unwrap(),
+            n("4")
+        );
+        assert!(matches!(
+            run_program("let f = fn(x, y) => x - y; f(3, x: 1)"),
+            Err(Error::RuntimeError(RuntimeError::DuplicateArgument(name, arg)))
+                if name == "f" && arg == "x"
+        ));
+        assert!(matches!(
+            run_program("let f = fn(x, y) => x - y; f(x: 1)"),
+            Err(Error::RuntimeError(RuntimeError::ArityMismatch(name, 2, 1))) if name == "f"
+        ));
+    }
+
+    #[test]
+    fn test_eval_function_argument() {
+        fn apply(args: &Arguments) -> Result<Value, Error> {
+            match args.positional.as_slice() {
+                [Value::Function(f), value] => f.call("f", &Arguments::new([value.clone()])),
+                _ => Err(RuntimeError::InvalidOperandType.into()),
+            }
+        }
+
+        let mut env = Environment::new();
+        env.register("apply", apply);
+        let expr = crate::parse("apply(fn(x) => x * x, 7)").unwrap();
+        assert_eq!(eval(&expr, &env).unwrap(), n("49"));
+
+        let f = run("fn(x) => x").unwrap();
+        assert_eq!(f, f.clone());
+        assert_ne!(f, run("fn(x) => x").unwrap());
+    }
+
+    #[test]
+    fn test_eval_list() {
+        assert_eq!(
+            run(r#"[1 + 1, "two", :three, [4]]"#).unwrap().to_string(),
+            r#"[2, "two", :three, [4]]"#
+        );
+        assert_eq!(run("[1, 2] == [1, 2.0]").unwrap(), Value::from(true));
+        assert_eq!(run("len([1, 2, 3])").unwrap(), n("3"));
+    }
+
+    #[test]
+    fn test_eval_index() {
+        assert_eq!(run("[10, 20, 30][0]").unwrap(), n("10"));
+        assert_eq!(run("[10, 20, 30][-1]").unwrap(), n("30"));
+        assert_eq!(run("[[1, 2], [3, 4]][1][0]").unwrap(), n("3"));
+        assert_eq!(run(r#""héllo"[1]"#).unwrap(), Value::from("é"));
+        assert!(matches!(
+            run("[10, 20, 30][3]"),
+            Err(Error::RuntimeError(RuntimeError::IndexOutOfRange(i, 3))) if i == "3"
+        ));
+        assert!(matches!(
+            run("[10, 20, 30][-4]"),
+            Err(Error::RuntimeError(RuntimeError::IndexOutOfRange(i, 3))) if i == "-4"
+        ));
+        assert!(matches!(
+            run("[1][0.5]"),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+        assert!(matches!(
+            run("1[0]"),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+    }
+
+    #[test]
+    fn test_eval_record() {
+        let rec = r#"{name: "x", "order id": 3, nested: {tags: [:a, :b]}}"#;
+        assert_eq!(
+            run(rec).unwrap().to_string(),
+            r#"{name: "x", "order id": 3, nested: {tags: [:a, :b]}}"#
+        );
+        assert_eq!(run(&format!("{rec}.name")).unwrap(), Value::from("x"));
+        assert_eq!(run(&format!(r#"{rec}."order id""#)).unwrap(), n("3"));
+        assert_eq!(
+            run("{`order id`: 3, `price.usd`: 4}.`price.usd`").unwrap(),
+            n("4")
+        );
+        assert_eq!(run(&format!(r#"{rec}["order id"]"#)).unwrap(), n("3"));
+        assert_eq!(run(&format!("{rec}[(:name)]")).unwrap(), Value::from("x"));
+        assert_eq!(
+            run(&format!("{rec}.nested.tags[-1]")).unwrap(),
+            Value::Symbol("b".into())
+        );
+        assert_eq!(
+            run("{a: 1, b: 2} == {b: 2, a: 1}").unwrap(),
+            Value::from(true)
+        );
+        assert!(matches!(
+            run(&format!("{rec}.missing")),
+            Err(Error::RuntimeError(RuntimeError::UnknownField(name))) if name == "missing"
+        ));
+        assert!(matches!(
+            run(&format!("{rec}[0]")),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+        assert!(matches!(
+            run("[1].name"),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+    }
+
+    #[test]
+    fn test_eval_interpolation() {
+        let mut env = Environment::new();
+        env.set("count", 1200);
+        env.set("file", "a.csv");
+        env.set("status", Value::Symbol("ok".into()));
+        assert_eq!(
+            run_in(
+                r#""rows: ${count / 100} in ${file} (${status}, ${count > 0})""#,
+                &env
+            )
+            .unwrap(),
+            Value::from("rows: 12 in a.csv (ok, true)")
+        );
+        assert!(matches!(
+            run(r#""${missing}""#),
+            Err(Error::RuntimeError(RuntimeError::UndefinedVariable(name))) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn test_eval_if() {
+        let tier = "if amount > 100 then :gold else if amount > 10 then :silver else :std";
+        let mut env = Environment::new();
+        for (amount, expected) in [(500, "gold"), (50, "silver"), (5, "std")] {
+            env.set("amount", amount);
+            assert_eq!(run_in(tier, &env).unwrap(), Value::Symbol(expected.into()));
+        }
+        assert_eq!(run("if false then 1 / 0 else 2").unwrap(), n("2"));
+        assert!(matches!(
+            run("if 1 then 2 else 3"),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+    }
+
+    #[test]
+    fn test_eval_regex() {
+        let mut env = Environment::new();
+        env.set("line", "2024-01-31 ERROR disk full");
+        assert_eq!(
+            run_in(r#"line =~ ~r"^\d{4}-\d{2}-\d{2} ""#, &env).unwrap(),
+            Value::from(true)
+        );
+        assert_eq!(
+            run_in(r#"line =~ ~r"error"i"#, &env).unwrap(),
+            Value::from(true)
+        );
+        assert_eq!(
+            run_in(r#"line !~ ~r"error""#, &env).unwrap(),
+            Value::from(true)
+        );
+        assert_eq!(run_in(r#"null =~ ~r"a""#, &env).unwrap(), Value::Null);
+        assert_eq!(
+            run_in(
+                r#"match line { ~r"WARN" => :warn, ~r"ERROR" => :error, _ => :info }"#,
+                &env
+            )
+            .unwrap(),
+            Value::Symbol("error".into())
+        );
+        assert_eq!(
+            run_in(r#"~r"a" == ~r"a""#, &env).unwrap(),
+            Value::from(true)
+        );
+        assert!(matches!(
+            run_in(r#"line =~ "error""#, &env),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+        assert_eq!(
+            run_in(r##"~r#"say "hi""#i"##, &env).unwrap().to_string(),
+            r##"~r#"say "hi""#i"##
+        );
+    }
+
+    #[test]
+    fn test_eval_match() {
+        let classify = r#"match x {
+            :ok => "ok",
+            0 => "zero",
+            -1 => "minus one",
+            [] => "empty",
+            [only] => "one: " + only,
+            [first, ..rest] => first + " then " + rest[0],
+            {kind: :refund, amount} => "refund of " + amount,
+            {"order id": id} => "order " + id,
+            other => "other"
+        }"#;
+        let expr = crate::parse(classify).unwrap();
+        let cases = [
+            (":ok", "ok"),
+            ("0", "zero"),
+            ("-1", "minus one"),
+            ("[]", "empty"),
+            (r#"["a"]"#, "one: a"),
+            (r#"["a", "b", "c"]"#, "a then b"),
+            (r#"{kind: :refund, amount: "5", at: 1}"#, "refund of 5"),
+            (r#"{kind: :sale, amount: "5"}"#, "other"),
+            (r#"{"order id": "7"}"#, "order 7"),
+            ("true", "other"),
+        ];
+        for (input, expected) in cases {
+            let mut env = Environment::new();
+            env.set("x", run(input).unwrap());
+            assert_eq!(eval(&expr, &env).unwrap(), Value::from(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_eval_match_bindings_are_scoped() {
+        let mut env = Environment::new();
+        env.set("x", 1);
+        assert_eq!(run_in("match [2] { [x] => x } + x", &env).unwrap(), n("3"));
+        assert!(matches!(
+            run("match :b { :a => 1, [_] => 2 }"),
+            Err(Error::RuntimeError(RuntimeError::NonExhaustiveMatch(value))) if value == ":b"
+        ));
+    }
+
+    #[test]
+    fn test_eval_slice() {
+        let xs = "[1, 2, 3, 4, 5]";
+        assert_eq!(run(&format!("{xs}[1:3]")).unwrap(), list(&["2", "3"]));
+        assert_eq!(run(&format!("{xs}[:2]")).unwrap(), list(&["1", "2"]));
+        assert_eq!(run(&format!("{xs}[-2:]")).unwrap(), list(&["4", "5"]));
+        assert_eq!(run(&format!("{xs}[:]")).unwrap(), run(xs).unwrap());
+        assert_eq!(run(&format!("{xs}[5:]")).unwrap(), list(&[]));
+        assert_eq!(run(r#""hello"[1:-1]"#).unwrap(), Value::from("ell"));
+        assert!(matches!(
+            run(&format!("{xs}[0:6]")),
+            Err(Error::RuntimeError(RuntimeError::IndexOutOfRange(i, 5))) if i == "6"
+        ));
+        assert!(matches!(
+            run(&format!("{xs}[3:1]")),
+            Err(Error::RuntimeError(RuntimeError::InvalidSlice(3, 1)))
+        ));
+    }
+
+    #[test]
+    fn test_eval_range() {
+        assert_eq!(run("1..=3").unwrap().to_string(), "1..=3");
+        assert_eq!(run("len(0..10 ** 12 step 3)").unwrap(), n("333333333334"));
+        assert_eq!(run("(0..10 ** 12)[-1]").unwrap(), n("999999999999"));
+        assert_eq!(run("(10..0 step -3)[2]").unwrap(), n("4"));
+        assert_eq!(run("len(5..1)").unwrap(), n("0"));
+        assert_eq!(run("1..null").unwrap(), Value::Null);
+        assert!(matches!(
+            run("0..10 step 0"),
+            Err(Error::RuntimeError(RuntimeError::ZeroStep))
+        ));
+        assert!(matches!(
+            run("0..1.5"),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+        assert!(matches!(
+            run("(0..3)[3]"),
+            Err(Error::RuntimeError(RuntimeError::IndexOutOfRange(i, 3))) if i == "3"
+        ));
+    }
+
+    #[test]
+    fn test_eval_in() {
+        for (input, expected) in [
+            ("4 in 0..10 step 2", true),
+            ("5 in 0..10 step 2", false),
+            ("10 in 0..10", false),
+            ("10 in 0..=10", true),
+            ("2.5 in 0..10", false),
+            ("2 in [1, 2, 3]", true),
+            (r#""b" in ["a"]"#, false),
+            (r#""ell" in "hello""#, true),
+            (r#":name in {name: 1}"#, true),
+            (r#""age" in {name: 1}"#, false),
+        ] {
+            assert_eq!(run(input).unwrap(), Value::from(expected), "{input}");
+        }
+        assert_eq!(run("null in [null]").unwrap(), Value::Null);
+        assert!(matches!(
+            run(r#"1 in "123""#),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+    }
+
+    #[test]
+    fn test_eval_slice_by_range() {
+        let xs = "[1, 2, 3, 4, 5]";
+        assert_eq!(run(&format!("{xs}[1..3]")).unwrap(), list(&["2", "3"]));
+        assert_eq!(
+            run(&format!("{xs}[1..=3]")).unwrap(),
+            list(&["2", "3", "4"])
+        );
+        assert_eq!(
+            run(&format!("{xs}[0..len({xs}) step 2]")).unwrap(),
+            list(&["1", "3", "5"])
+        );
+        assert_eq!(
+            run(&format!("{xs}[4..=0 step -2]")).unwrap(),
+            list(&["5", "3", "1"])
+        );
+        assert_eq!(run(&format!("{xs}[3..3]")).unwrap(), list(&[]));
+        assert_eq!(
+            run(r#""hello"[4..=0 step -1]"#).unwrap(),
+            Value::from("olleh")
+        );
+        assert!(matches!(
+            run(&format!("{xs}[3..10 ** 9]")),
+            Err(Error::RuntimeError(RuntimeError::IndexOutOfRange(i, 5))) if i == "5"
+        ));
+    }
+
+    #[test]
+    fn test_eval_function_call() {
+        assert_eq!(run("round(3.14159, 2)").unwrap(), n("3.14"));
+        assert_eq!(run(r#"upper(trim("  hi  "))"#).unwrap(), Value::from("HI"));
+        assert!(matches!(
+            run("nope(1)"),
+            Err(Error::RuntimeError(RuntimeError::UndefinedFunction(name))) if name == "nope"
+        ));
+    }
+
+    #[test]
+    fn test_eval_call_of_non_function() {
+        let mut env = Environment::new();
+        env.set("f", 1);
+        env.set("len", 3);
+        for input in ["2 |> f", "f(2)", "2 |> f(3)"] {
+            assert_eq!(
+                run_in(input, &env).unwrap_err().to_string(),
+                "Runtime Error: f is not a function (got number)",
+                "{input}"
+            );
+        }
+        // A variable that is not a function leaves a builtin of the same name callable.
+        assert_eq!(run_in(r#"len("ab")"#, &env).unwrap(), n("2"));
+    }
+
+    #[test]
+    fn test_eval_pipe() {
+        assert_eq!(
+            run(r#""  hi  " |> trim |> upper"#).unwrap(),
+            Value::from("HI")
+        );
+        assert_eq!(run("3.14159 |> round(2)").unwrap(), n("3.14"));
+    }
+
+    #[test]
+    fn test_eval_pipe_into_function_value() {
+        assert_eq!(run("1 |> fn(x) => x + 1").unwrap(), n("2"));
+        assert_eq!(run("1 |> (fn(x) => x + 1)").unwrap(), n("2"));
+        assert_eq!(
+            run("1 |> (fn(x) => x + 1) |> fn(x) => x * 10").unwrap(),
+            n("20")
+        );
+        assert_eq!(run("2 |> (fn(x, y) => x - y)(5)").unwrap(), n("-3"));
+        assert_eq!(run("[fn(x) => x * 2][0](4)").unwrap(), n("8"));
+        assert_eq!(
+            run("1 |> 2").unwrap_err().to_string(),
+            "Runtime Error: 2 is not a function (got number)"
+        );
+    }
+
+    #[test]
+    fn test_eval_call_of_expression() {
+        assert_eq!(run("(fn(x) => x)(1)").unwrap(), n("1"));
+        assert_eq!(run("(fn(n) => fn(x) => x + n)(2)(40)").unwrap(), n("42"));
+        assert_eq!(
+            run("{f: fn() => :ok}.f()").unwrap(),
+            Value::Symbol("ok".to_string())
+        );
+        assert!(matches!(
+            run("(fn(x) => x)(1, 2)"),
+            Err(Error::RuntimeError(RuntimeError::ArityMismatch(name, 1, 2))) if name == "fn(x)"
+        ));
+        assert!(matches!(
+            run("(1)(2)"),
+            Err(Error::RuntimeError(RuntimeError::NotAFunction(name, kind)))
+                if name == "1" && kind == "number"
+        ));
+    }
+
+    #[test]
+    fn test_eval_nested_pipelines() {
+        assert_eq!(
+            run(r#"("  hi  " |> trim) + ("b" |> upper)"#).unwrap(),
+            Value::from("hiB")
+        );
+        assert_eq!(
+            run(r#"round("  2.345 " |> trim |> len, 1)"#).unwrap(),
+            n("5")
+        );
+        assert_eq!(
+            run(r#"[" a " |> trim, {v: "b" |> upper}.v]"#).unwrap(),
+            Value::from(vec![Value::from("a"), Value::from("B")])
+        );
+        assert_eq!(
+            run(r#""  hi  " |> (trim |> upper)"#).unwrap(),
+            Value::from("HI")
+        );
+    }
+
+    #[test]
+    fn test_eval_host_function() {
+        fn double(args: &Arguments) -> Result<Value, Error> {
+            match args.positional.as_slice() {
+                [Value::Number(n)] => Ok(Value::Number(Numeric::new(n.as_ref() * 2))),
+                _ => Err(RuntimeError::InvalidOperandType.into()),
+            }
+        }
+
+        let mut env = Environment::new();
+        env.register("double", double);
+        assert_eq!(run_in("21 |> double", &env).unwrap(), n("42"));
+    }
+
+    #[test]
+    fn test_eval_named_arguments() {
+        fn write(args: &Arguments) -> Result<Value, Error> {
+            let pretty = args.get("pretty").cloned().unwrap_or(Value::from(false));
+            Ok(Value::String(format!(
+                "{} pretty={}",
+                args.positional[0], pretty
+            )))
+        }
+
+        let mut env = Environment::new();
+        env.register("WRITE", write);
+        let expr = crate::parse(r#""out.json" |> WRITE(pretty: !false)"#).unwrap();
+        assert_eq!(
+            eval(&expr, &env).unwrap(),
+            Value::from("out.json pretty=true")
+        );
+        let expr = crate::parse(r#"WRITE("out.json")"#).unwrap();
+        assert_eq!(
+            eval(&expr, &env).unwrap(),
+            Value::from("out.json pretty=false")
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! The standard builtin functions.
+use bigdecimal::BigDecimal;
+use bigdecimal::RoundingMode;
+use bigdecimal::ToPrimitive;
+
+use super::env::Arguments;
+use super::env::Environment;
+use super::value::Value;
+use crate::ast::Numeric;
+use crate::errors::RuntimeError;
+use crate::Error;
+
+/// Registers every standard builtin into `env`.
+pub(crate) fn register_all(env: &mut Environment) {
+    env.register("abs", abs);
+    env.register("ceil", ceil);
+    env.register("floor", floor);
+    env.register("round", round);
+    env.register("trim", trim);
+    env.register("lower", lower);
+    env.register("upper", upper);
+    env.register("len", len);
+}
+
+/// Checks that a builtin taking exactly `N` positional arguments and no named ones was called correctly.
+fn expect_args<'a, const N: usize>(
+    name: &str,
+    args: &'a Arguments,
+) -> Result<&'a [Value; N], Error> {
+    if let Some((unknown, _)) = args.named.first() {
+        return Err(RuntimeError::UnknownArgument(name.to_string(), unknown.clone()).into());
+    }
+    args.positional
+        .as_slice()
+        .try_into()
+        .map_err(|_| RuntimeError::ArityMismatch(name.to_string(), N, args.positional.len()).into())
+}
+
+fn number(value: &Value) -> Result<&BigDecimal, Error> {
+    match value {
+        Value::Number(n) => Ok(n.as_ref()),
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+fn string(value: &Value) -> Result<&str, Error> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+fn abs(args: &Arguments) -> Result<Value, Error> {
+    let [x] = expect_args("abs", args)?;
+    Ok(Value::Number(Numeric::new(number(x)?.abs())))
+}
+
+fn ceil(args: &Arguments) -> Result<Value, Error> {
+    let [x] = expect_args("ceil", args)?;
+    Ok(Value::Number(Numeric::new(
+        number(x)?.with_scale_round(0, RoundingMode::Ceiling),
+    )))
+}
+
+fn floor(args: &Arguments) -> Result<Value, Error> {
+    let [x] = expect_args("floor", args)?;
+    Ok(Value::Number(Numeric::new(
+        number(x)?.with_scale_round(0, RoundingMode::Floor),
+    )))
+}
+
+/// `round(x, digits)`: rounds half away from zero to `digits` fractional digits.
+fn round(args: &Arguments) -> Result<Value, Error> {
+    let [x, digits] = expect_args("round", args)?;
+    let digits = number(digits)?;
+    let digits = Some(digits)
+        .filter(|d| d.is_integer())
+        .and_then(ToPrimitive::to_i64)
+        .ok_or(RuntimeError::InvalidOperandType)?;
+    Ok(Value::Number(Numeric::new(
+        number(x)?.with_scale_round(digits, RoundingMode::HalfUp),
+    )))
+}
+
+fn trim(args: &Arguments) -> Result<Value, Error> {
+    let [s] = expect_args("trim", args)?;
+    Ok(Value::String(string(s)?.trim().to_string()))
+}
+
+fn lower(args: &Arguments) -> Result<Value, Error> {
+    let [s] = expect_args("lower", args)?;
+    Ok(Value::String(string(s)?.to_lowercase()))
+}
+
+fn upper(args: &Arguments) -> Result<Value, Error> {
+    let [s] = expect_args("upper", args)?;
+    Ok(Value::String(string(s)?.to_uppercase()))
+}
+
+/// `len(s)`: the number of characters in a string, or of items in a list or range.
+fn len(args: &Arguments) -> Result<Value, Error> {
+    let [s] = expect_args("len", args)?;
+    let len = match s {
+        Value::List(items) => items.len(),
+        Value::Range(range) => return Ok(Value::Number(Numeric::new(range.len()))),
+        _ => string(s)?.chars().count(),
+    };
+    Ok(Value::from(len as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use super::*;
+    use crate::eval::Builtin;
+
+    fn call<const N: usize>(f: Builtin, args: [Value; N]) -> Result<Value, Error> {
+        f(&Arguments::new(args))
+    }
+
+    fn n(s: &str) -> Value {
+        Value::Number(Numeric::new(BigDecimal::from_str(s).unwrap()))
+    }
+
+    #[test]
+    fn test_rounding() {
+        assert_eq!(call(ceil, [n("3.14")]).unwrap(), n("4"));
+        assert_eq!(call(floor, [n("-3.14")]).unwrap(), n("-4"));
+        assert_eq!(call(round, [n("3.145"), n("2")]).unwrap(), n("3.15"));
+        assert_eq!(call(abs, [n("-2.5")]).unwrap(), n("2.5"));
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(call(trim, ["  hi ".into()]).unwrap(), Value::from("hi"));
+        assert_eq!(call(upper, ["hi".into()]).unwrap(), Value::from("HI"));
+        assert_eq!(call(lower, ["HI".into()]).unwrap(), Value::from("hi"));
+        assert_eq!(call(len, ["héllo".into()]).unwrap(), Value::from(5));
+        assert_eq!(
+            call(len, [Value::from(vec![Value::from(1), Value::from("a")])]).unwrap(),
+            Value::from(2)
+        );
+    }
+
+    #[test]
+    fn test_unknown_named_argument() {
+        let args = Arguments {
+            positional: vec!["hi".into()],
+            named: vec![("chars".into(), " ".into())],
+        };
+        assert!(matches!(
+            trim(&args),
+            Err(Error::RuntimeError(RuntimeError::UnknownArgument(name, arg)))
+                if name == "trim" && arg == "chars"
+        ));
+    }
+
+    #[test]
+    fn test_arity_and_types() {
+        assert!(matches!(
+            call(trim, []),
+            Err(Error::RuntimeError(RuntimeError::ArityMismatch(name, 1, 0))) if name == "trim"
+        ));
+        assert!(matches!(
+            call(floor, ["x".into()]),
+            Err(Error::RuntimeError(RuntimeError::InvalidOperandType))
+        ));
+    }
+}
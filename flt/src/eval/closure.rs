@@ -0,0 +1,172 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::env::Arguments;
+use super::env::Environment;
+use super::value::Value;
+use crate::ast::Expr;
+use crate::errors::RuntimeError;
+use crate::Error;
+
+/// How deeply function calls may nest before evaluation gives up, so that runaway recursion
+/// is an error rather than endless growth. The stack itself grows as evaluation needs it.
+const MAX_CALL_DEPTH: usize = 10_000;
+
+thread_local! {
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A function value: the parameters and body of a lambda or `def`, and the environment it was
+/// created in. A function defined with `def` also sees every `def` of its program, itself
+/// included, under their names, except where it captured a variable of the same name.
+#[derive(Clone)]
+pub struct Closure {
+    function: Rc<Function>,
+    defs: Option<Rc<Defs>>,
+}
+
+struct Function {
+    name: Option<String>,
+    params: Vec<String>,
+    body: Expr,
+    env: Rc<Environment>,
+}
+
+/// The functions defined with `def` in a program, which may call one another. A def's captured
+/// environment leaves them out, and they are bound from here when it is called instead, so that
+/// they do not keep each other alive through it.
+#[derive(Default)]
+pub(crate) struct Defs(RefCell<Vec<Rc<Function>>>);
+
+impl Defs {
+    /// Defines the function `name` in this scope, capturing `env`, and returns it.
+    pub(crate) fn define(
+        self: &Rc<Self>,
+        name: impl Into<String>,
+        params: Vec<String>,
+        body: Expr,
+        mut env: Environment,
+    ) -> Closure {
+        env.retain(|_, value| !matches!(value, Value::Function(f) if f.is_def_of(self)));
+        let function = Rc::new(Function {
+            name: Some(name.into()),
+            params,
+            body,
+            env: Rc::new(env),
+        });
+        self.0.borrow_mut().push(Rc::clone(&function));
+        Closure {
+            function,
+            defs: Some(Rc::clone(self)),
+        }
+    }
+}
+
+impl Closure {
+    pub(crate) fn new(params: Vec<String>, body: Expr, env: Environment) -> Self {
+        Self {
+            function: Rc::new(Function {
+                name: None,
+                params,
+                body,
+                env: Rc::new(env),
+            }),
+            defs: None,
+        }
+    }
+
+    /// The name given by `def`, or `None` for a lambda.
+    pub fn name(&self) -> Option<&str> {
+        self.function.name.as_deref()
+    }
+
+    pub fn params(&self) -> &[String] {
+        &self.function.params
+    }
+
+    fn is_def_of(&self, defs: &Rc<Defs>) -> bool {
+        self.defs.as_ref().is_some_and(|d| Rc::ptr_eq(d, defs))
+    }
+
+    /// Calls the function with `args` bound to its parameters, on top of the captured
+    /// environment: positional arguments in order, then named arguments by parameter name.
+    /// `name` is what the function was called as, for error messages.
+    pub fn call(&self, name: &str, args: &Arguments) -> Result<Value, Error> {
+        let function = &self.function;
+        let mut bound: Vec<Option<&Value>> = vec![None; function.params.len()];
+        let given = args.positional.len() + args.named.len();
+        if args.positional.len() > bound.len() {
+            return Err(RuntimeError::ArityMismatch(name.to_string(), bound.len(), given).into());
+        }
+        for (slot, value) in bound.iter_mut().zip(&args.positional) {
+            *slot = Some(value);
+        }
+        for (arg, value) in &args.named {
+            let Some(i) = function.params.iter().position(|p| p == arg) else {
+                return Err(RuntimeError::UnknownArgument(name.to_string(), arg.clone()).into());
+            };
+            if bound[i].replace(value).is_some() {
+                return Err(RuntimeError::DuplicateArgument(name.to_string(), arg.clone()).into());
+            }
+        }
+        if bound.iter().any(Option::is_none) {
+            return Err(RuntimeError::ArityMismatch(name.to_string(), bound.len(), given).into());
+        }
+
+        let mut env = Environment::child(Rc::clone(&function.env));
+        if let Some(defs) = &self.defs {
+            for def in defs.0.borrow().iter() {
+                let name = def.name.as_deref().unwrap_or_default();
+                if env.get(name).is_none() {
+                    let def = Closure {
+                        function: Rc::clone(def),
+                        defs: Some(Rc::clone(defs)),
+                    };
+                    env.set(name, Value::Function(Rc::new(def)));
+                }
+            }
+        }
+        for (param, value) in function.params.iter().zip(bound) {
+            env.set(param.as_str(), value.cloned().unwrap_or(Value::Null));
+        }
+        let depth = CALL_DEPTH.get();
+        if depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::CallDepthExceeded(name.to_string()).into());
+        }
+        CALL_DEPTH.set(depth + 1);
+        let result = super::eval(&function.body, &env);
+        CALL_DEPTH.set(depth);
+        result
+    }
+}
+
+/// A function is equal only to itself.
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+/// Omits the captured environment, which may be large.
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("name", &self.function.name)
+            .field("params", &self.function.params)
+            .field("body", &self.function.body)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Prints the signature: `fn(x, y)`, or `fn name(x, y)` for a `def`.
+impl fmt::Display for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params = self.function.params.join(", ");
+        match &self.function.name {
+            Some(name) => write!(f, "fn {}({})", name, params),
+            None => write!(f, "fn({})", params),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::builtins;
+use super::value::Value;
+use crate::Error;
+
+/// A host function callable from the language: receives already evaluated arguments.
+pub type Builtin = fn(&Arguments) -> Result<Value, Error>;
+
+/// The evaluated arguments of a function call.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Arguments {
+    /// Positional arguments, in order. A piped value is the first of these.
+    pub positional: Vec<Value>,
+    /// Named arguments (`name: value`), in order. Names are unique.
+    pub named: Vec<(String, Value)>,
+}
+
+impl Arguments {
+    /// Constructs arguments with no named arguments.
+    pub fn new(values: impl Into<Vec<Value>>) -> Self {
+        Self {
+            positional: values.into(),
+            named: vec![],
+        }
+    }
+
+    /// Looks up the named argument `name`.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.named.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+/// The bindings an expression is evaluated against: variables and callable functions.
+///
+/// Variables live in frames: a function call binds its parameters in a new frame on top of the
+/// environment the function captured, which it shares rather than copies. The builtin functions
+/// are shared the same way, so cloning an environment copies only its innermost frame.
+#[derive(Clone, Debug)]
+pub struct Environment {
+    variables: HashMap<String, Value>,
+    parent: Option<Rc<Environment>>,
+    functions: Rc<HashMap<String, Builtin>>,
+}
+
+impl Environment {
+    /// Constructs an environment with no variables and the standard builtin functions.
+    pub fn new() -> Self {
+        let mut env = Self::empty();
+        builtins::register_all(&mut env);
+        env
+    }
+
+    /// Constructs an environment with no variables and no functions.
+    pub fn empty() -> Self {
+        Self {
+            variables: HashMap::new(),
+            parent: None,
+            functions: Rc::new(HashMap::new()),
+        }
+    }
+
+    /// Constructs an empty frame on top of `parent`, whose variables and functions it sees.
+    pub(crate) fn child(parent: Rc<Environment>) -> Self {
+        Self {
+            variables: HashMap::new(),
+            functions: Rc::clone(&parent.functions),
+            parent: Some(parent),
+        }
+    }
+
+    /// Binds `name` to `value` in the innermost frame, replacing any previous binding there and
+    /// shadowing any in outer frames.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        self.variables.insert(name.into(), value.into());
+    }
+
+    /// Looks up the variable `name`, innermost frame first.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        let mut frame = self;
+        loop {
+            if let Some(value) = frame.variables.get(name) {
+                return Some(value);
+            }
+            frame = frame.parent.as_deref()?;
+        }
+    }
+
+    /// Keeps only the variables of the innermost frame for which `keep` returns true.
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&str, &Value) -> bool) {
+        self.variables.retain(|name, value| keep(name, value));
+    }
+
+    /// Registers a host function under `name`, replacing any previous function of that name.
+    pub fn register(&mut self, name: impl Into<String>, function: Builtin) {
+        Rc::make_mut(&mut self.functions).insert(name.into(), function);
+    }
+
+    /// Looks up the function `name`.
+    pub fn function(&self, name: &str) -> Option<Builtin> {
+        self.functions.get(name).copied()
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_frame() {
+        let mut parent = Environment::new();
+        parent.set("x", 1);
+        parent.set("y", 2);
+        let parent = Rc::new(parent);
+        let mut child = Environment::child(Rc::clone(&parent));
+        child.set("x", 10);
+        assert_eq!(child.get("x"), Some(&Value::from(10)));
+        assert_eq!(child.get("y"), Some(&Value::from(2)));
+        assert_eq!(parent.get("x"), Some(&Value::from(1)));
+        assert!(child.function("len").is_some());
+        child.retain(|_, _| false);
+        assert_eq!(child.get("x"), Some(&Value::from(1)));
+    }
+}
@@ -0,0 +1,153 @@
+//! Lazy integer ranges.
+use std::fmt;
+
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::One;
+use bigdecimal::Signed;
+use bigdecimal::Zero;
+
+use crate::errors::RuntimeError;
+use crate::Error;
+
+/// The value of a range expression: the integers from `start` toward `end` in steps of `step`.
+/// They are computed as they are needed, so `0..10 ** 12` takes no more room than `0..10`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Range {
+    start: BigInt,
+    end: BigInt,
+    inclusive: bool,
+    step: BigInt,
+}
+
+impl Range {
+    /// The integers from `start` up to `end`, which is included only if `inclusive`, in steps of
+    /// `step`. A negative step counts down, so `5..0 step -1` is 5, 4, 3, 2, 1. A zero step is a
+    /// `ZeroStep` error.
+    pub fn new(start: BigInt, end: BigInt, inclusive: bool, step: BigInt) -> Result<Self, Error> {
+        if step.is_zero() {
+            return Err(RuntimeError::ZeroStep.into());
+        }
+        Ok(Range {
+            start,
+            end,
+            inclusive,
+            step,
+        })
+    }
+
+    /// How many integers the range holds.
+    pub fn len(&self) -> BigInt {
+        // Count from the start to the first integer past the end, in the direction of the step.
+        let direction = self.step.signum();
+        let past = match self.inclusive {
+            true => &self.end + &direction,
+            false => self.end.clone(),
+        };
+        let distance = (past - &self.start) * direction;
+        if !distance.is_positive() {
+            return BigInt::zero();
+        }
+        let step = self.step.abs();
+        (distance + &step - BigInt::one()) / step
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len().is_zero()
+    }
+
+    /// The integer at position `i`, counting from 0.
+    pub fn get(&self, i: &BigInt) -> Option<BigInt> {
+        (!i.is_negative() && *i < self.len()).then(|| &self.start + i * &self.step)
+    }
+
+    /// Whether `n` is one of the integers in the range.
+    pub fn contains(&self, n: &BigInt) -> bool {
+        let offset = n - &self.start;
+        (&offset % &self.step).is_zero() && self.get(&(offset / &self.step)).is_some()
+    }
+
+    /// The integers, in order.
+    pub fn iter(&self) -> impl Iterator<Item = BigInt> + '_ {
+        let mut remaining = self.len();
+        let mut next = self.start.clone();
+        std::iter::from_fn(move || {
+            if remaining.is_zero() {
+                return None;
+            }
+            remaining -= 1;
+            let n = next.clone();
+            next += &self.step;
+            Some(n)
+        })
+    }
+}
+
+/// Ranges print in their source form: `0..10`, `1..=5`, `0..100 step 10`.
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dots = if self.inclusive { "..=" } else { ".." };
+        write!(f, "{}{}{}", self.start, dots, self.end)?;
+        if !self.step.is_one() {
+            write!(f, " step {}", self.step)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: i64, end: i64, inclusive: bool, step: i64) -> Range {
+        Range::new(start.into(), end.into(), inclusive, step.into()).unwrap()
+    }
+
+    fn items(range: &Range) -> Vec<i64> {
+        range.iter().map(|n| i64::try_from(n).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_iter() {
+        assert_eq!(items(&range(0, 5, false, 1)), [0, 1, 2, 3, 4]);
+        assert_eq!(items(&range(1, 5, true, 1)), [1, 2, 3, 4, 5]);
+        assert_eq!(items(&range(0, 10, false, 3)), [0, 3, 6, 9]);
+        assert_eq!(items(&range(0, 9, true, 3)), [0, 3, 6, 9]);
+        assert_eq!(items(&range(5, 0, false, -2)), [5, 3, 1]);
+        assert_eq!(items(&range(5, 1, true, -2)), [5, 3, 1]);
+        assert!(items(&range(5, 0, false, 1)).is_empty());
+        assert!(items(&range(3, 3, false, 1)).is_empty());
+        assert_eq!(items(&range(3, 3, true, 1)), [3]);
+    }
+
+    #[test]
+    fn test_len_get_and_contains() {
+        let r = range(0, 10_i64.pow(15), false, 7);
+        assert_eq!(r.len(), BigInt::from(142_857_142_857_143_i64));
+        assert_eq!(r.get(&BigInt::from(2)), Some(BigInt::from(14)));
+        assert_eq!(r.get(&r.len()), None);
+        assert_eq!(r.get(&BigInt::from(-1)), None);
+        assert!(r.contains(&BigInt::from(21)));
+        assert!(!r.contains(&BigInt::from(22)));
+        assert!(!r.contains(&BigInt::from(-7)));
+        assert!(!r.contains(&BigInt::from(10_i64.pow(15) + 1)));
+        let down = range(10, 0, true, -5);
+        assert!(down.contains(&BigInt::from(0)));
+        assert!(!down.contains(&BigInt::from(15)));
+        assert!(range(0, 0, false, 1).is_empty());
+    }
+
+    #[test]
+    fn test_zero_step() {
+        assert!(matches!(
+            Range::new(0.into(), 1.into(), false, 0.into()),
+            Err(Error::RuntimeError(RuntimeError::ZeroStep))
+        ));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(range(0, 10, false, 1).to_string(), "0..10");
+        assert_eq!(range(1, 5, true, 1).to_string(), "1..=5");
+        assert_eq!(range(10, -10, false, -5).to_string(), "10..-10 step -5");
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+
+use super::value::write_item;
+use super::value::write_name;
+use super::value::Value;
+
+/// The value of a record: fields in the order they were written, with unique keys.
+#[derive(Clone, Debug, Default)]
+pub struct Record {
+    fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks up the field `key`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Sets the field `key`, keeping its position if it exists and adding it at the end otherwise.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        let (key, value) = (key.into(), value.into());
+        match self.fields.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((key, value)),
+        }
+    }
+
+    /// The fields, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.fields.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// Records are equal when they have the same fields, in any order.
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Record {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut record = Record::new();
+        for (key, value) in iter {
+            record.insert(key, value);
+        }
+        record
+    }
+}
+
+/// Prints `{name: "x", "order id": 3}`: keys as symbol names, values as list items.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_name(f, key)?;
+            write!(f, ": ")?;
+            write_item(f, value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_keeps_order() {
+        let mut record: Record = [("b", 1), ("a", 2)].into_iter().collect();
+        record.insert("b", 3);
+        record.insert("c", "x");
+        assert_eq!(record.to_string(), r#"{b: 3, a: 2, c: "x"}"#);
+    }
+
+    #[test]
+    fn test_equality_ignores_order() {
+        let ab: Record = [("a", 1), ("b", 2)].into_iter().collect();
+        let ba: Record = [("b", 2), ("a", 1)].into_iter().collect();
+        assert_eq!(ab, ba);
+        assert_ne!(ab, [("a", 1)].into_iter().collect());
+    }
+
+    #[test]
+    fn test_display_quotes_keys() {
+        let record: Record = [("order id", 1)].into_iter().collect();
+        assert_eq!(record.to_string(), r#"{"order id": 1}"#);
+    }
+}
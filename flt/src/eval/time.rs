@@ -0,0 +1,107 @@
+//! Arithmetic on dates, timestamps and durations.
+use std::cmp::Ordering;
+use std::fmt;
+
+use bigdecimal::BigDecimal;
+use bigdecimal::RoundingMode;
+use bigdecimal::ToPrimitive;
+use chrono::DateTime;
+use chrono::TimeDelta;
+use chrono::Utc;
+
+use super::value::Value;
+use crate::ast::Numeric;
+use crate::errors::RuntimeError;
+use crate::Error;
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+/// The instant a date or timestamp stands for; a date stands for its midnight in UTC.
+pub(super) fn instant(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::Date(date) => Some(date.and_time(Default::default()).and_utc()),
+        Value::Timestamp(timestamp) => Some(*timestamp),
+        _ => None,
+    }
+}
+
+/// Orders two dates or timestamps by the instants they stand for.
+pub(super) fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    Some(instant(left)?.cmp(&instant(right)?))
+}
+
+/// `value + by`, for a date or timestamp. A date moved by whole days stays a date; moved by
+/// anything else it becomes a timestamp.
+pub(super) fn shift(value: Value, by: TimeDelta) -> Result<Value, Error> {
+    let whole_days = by.num_seconds() % 86_400 == 0 && by.subsec_nanos() == 0;
+    let shifted = match value {
+        Value::Date(date) if whole_days => date.checked_add_signed(by).map(Value::Date),
+        value => instant(&value)
+            .and_then(|instant| instant.checked_add_signed(by))
+            .map(Value::Timestamp),
+    };
+    shifted.ok_or_else(|| RuntimeError::TimeOutOfRange.into())
+}
+
+/// `left - right`, for dates or timestamps: the duration between them.
+pub(super) fn since(left: &Value, right: &Value) -> Result<Value, Error> {
+    match (instant(left), instant(right)) {
+        (Some(left), Some(right)) => Ok(Value::Duration(left.signed_duration_since(right))),
+        _ => Err(RuntimeError::InvalidOperandType.into()),
+    }
+}
+
+/// The length of `duration` in nanoseconds.
+pub(super) fn nanos(duration: &TimeDelta) -> BigDecimal {
+    BigDecimal::from(duration.num_seconds()) * NANOS_PER_SECOND
+        + BigDecimal::from(duration.subsec_nanos())
+}
+
+/// The duration `nanos` nanoseconds long, rounded to the nearest nanosecond.
+pub(super) fn from_nanos(nanos: BigDecimal) -> Result<Value, Error> {
+    nanos
+        .with_scale_round(0, RoundingMode::HalfEven)
+        .to_i128()
+        .and_then(|n| {
+            let seconds = i64::try_from(n.div_euclid(NANOS_PER_SECOND.into())).ok()?;
+            let nanos = n.rem_euclid(NANOS_PER_SECOND.into()) as u32;
+            TimeDelta::new(seconds, nanos)
+        })
+        .map(Value::Duration)
+        .ok_or_else(|| RuntimeError::TimeOutOfRange.into())
+}
+
+/// `left / right`, for durations: how many times `right` fits in `left`.
+pub(super) fn ratio(left: &TimeDelta, right: &TimeDelta) -> Result<Value, Error> {
+    if right.is_zero() {
+        return Err(RuntimeError::DivisionByZero.into());
+    }
+    Ok(Value::Number(Numeric::new(nanos(left) / nanos(right))))
+}
+
+/// Writes a duration in the units of its literals, largest first: `1d2h`, `1m30s`, `-250ms`.
+pub(super) fn write_duration(f: &mut fmt::Formatter<'_>, duration: &TimeDelta) -> fmt::Result {
+    if duration.is_zero() {
+        return write!(f, "0s");
+    }
+    if *duration < TimeDelta::zero() {
+        write!(f, "-")?;
+    }
+    let duration = duration.abs();
+    let (seconds, nanos) = (duration.num_seconds(), i64::from(duration.subsec_nanos()));
+    let parts = [
+        (seconds / 86_400, "d"),
+        (seconds % 86_400 / 3_600, "h"),
+        (seconds % 3_600 / 60, "m"),
+        (seconds % 60, "s"),
+        (nanos / 1_000_000, "ms"),
+        (nanos / 1_000 % 1_000, "us"),
+        (nanos % 1_000, "ns"),
+    ];
+    for (count, unit) in parts {
+        if count != 0 {
+            write!(f, "{}{}", count, unit)?;
+        }
+    }
+    Ok(())
+}
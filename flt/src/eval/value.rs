@@ -0,0 +1,277 @@
+use std::fmt;
+use std::rc::Rc;
+
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::TimeDelta;
+use chrono::Utc;
+
+use super::closure::Closure;
+use super::range::Range;
+use super::record::Record;
+use super::time::instant;
+use super::time::write_duration;
+use crate::ast::Identifier;
+use crate::ast::Literal;
+use crate::ast::Numeric;
+use crate::ast::Regex;
+
+/// A runtime value produced by evaluating an expression.
+#[derive(Clone, Debug)]
+pub enum Value {
+    /// A missing value, such as an empty cell.
+    Null,
+    Number(Numeric),
+    String(String),
+    Boolean(bool),
+    Symbol(String),
+    Date(NaiveDate),
+    Timestamp(DateTime<Utc>),
+    Duration(TimeDelta),
+    Regex(Regex),
+    Range(Rc<Range>),
+    Function(Rc<Closure>),
+    /// The items of a list, shared so that reading a variable does not copy them.
+    List(Rc<[Value]>),
+    Record(Record),
+}
+
+impl Value {
+    /// The name of this value's type, as used in runtime error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Boolean(_) => "boolean",
+            Value::Symbol(_) => "symbol",
+            Value::Date(_) => "date",
+            Value::Timestamp(_) => "timestamp",
+            Value::Duration(_) => "duration",
+            Value::Regex(_) => "regex",
+            Value::Range(_) => "range",
+            Value::Function(_) => "function",
+            Value::List(_) => "list",
+            Value::Record(_) => "record",
+        }
+    }
+
+    /// This value as text in an interpolated string: symbols by name (`:ok` gives `ok`), dates
+    /// and timestamps without their `@`, and anything else as it displays, so strings are
+    /// unquoted and numbers plain (`1.50` gives `1.5`).
+    pub fn to_text(&self) -> String {
+        match self {
+            Value::Symbol(name) => name.clone(),
+            Value::Date(_) | Value::Timestamp(_) => self.to_string()[1..].to_string(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Values are equal when they have the same type and are equal as that type, except that a date
+/// equals a timestamp at its midnight in UTC, as they are ordered.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) | (Value::Symbol(a), Value::Symbol(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Date(_) | Value::Timestamp(_), Value::Date(_) | Value::Timestamp(_)) => {
+                instant(self) == instant(other)
+            }
+            (Value::Duration(a), Value::Duration(b)) => a == b,
+            (Value::Regex(a), Value::Regex(b)) => a == b,
+            (Value::Range(a), Value::Range(b)) => a == b,
+            (Value::Function(a), Value::Function(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Record(a), Value::Record(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl From<Literal> for Value {
+    fn from(value: Literal) -> Self {
+        match value {
+            Literal::Null => Value::Null,
+            Literal::Number(n) => Value::Number(n),
+            Literal::String(s) => Value::String(s),
+            Literal::Boolean(b) => Value::Boolean(b),
+            Literal::Symbol(s) => Value::Symbol(s),
+            Literal::Date(d) => Value::Date(d),
+            Literal::Timestamp(t) => Value::Timestamp(t),
+            Literal::Duration(d) => Value::Duration(d),
+            Literal::Regex(r) => Value::Regex(r),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Number(Numeric::new(value))
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Value::List(value.into())
+    }
+}
+
+impl From<Record> for Value {
+    fn from(value: Record) -> Self {
+        Value::Record(value)
+    }
+}
+
+impl From<Range> for Value {
+    fn from(value: Range) -> Self {
+        Value::Range(Rc::new(value))
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+/// Strings print without quotes; symbols, dates, timestamps, durations, regexes and ranges print
+/// in their source form (`:foo`, `:"hello world"`, `@2024-01-31`, `@2024-01-31T12:00:00Z`,
+/// `1h30m`, `~r"^a"i`, `0..10 step 2`);
+/// functions print their signature (`fn(x, y)`); lists and records print their items with strings
+/// quoted (`[1, "a", :b]`, `{name: "a"}`).
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Symbol(s) => {
+                write!(f, ":")?;
+                write_name(f, s)
+            }
+            Value::Date(d) => write!(f, "@{}", d),
+            Value::Timestamp(t) => write!(f, "@{}", t.format("%Y-%m-%dT%H:%M:%S%.fZ")),
+            Value::Duration(d) => write_duration(f, d),
+            Value::Regex(r) => write!(f, "{}", r),
+            Value::Range(r) => write!(f, "{}", r),
+            Value::Function(function) => write!(f, "{}", function),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_item(f, item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Record(record) => write!(f, "{}", record),
+        }
+    }
+}
+
+/// Writes a symbol name or record key: bare if it is an identifier, quoted otherwise.
+pub(super) fn write_name(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    if Identifier::is_valid(name) {
+        write!(f, "{}", name)
+    } else {
+        write!(f, "{:?}", name)
+    }
+}
+
+/// Writes a value inside a list or record, where strings are quoted.
+pub(super) fn write_item(f: &mut fmt::Formatter<'_>, value: &Value) -> fmt::Result {
+    match value {
+        Value::String(s) => write!(f, "{:?}", s),
+        _ => write!(f, "{}", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use bigdecimal::BigDecimal;
+
+    use super::*;
+
+    #[test]
+    fn test_display_number() {
+        assert_eq!(Value::from(42).to_string(), "42");
+        assert_eq!(Value::from(100).to_string(), "100");
+        assert_eq!(
+            Value::Number(Numeric::new(BigDecimal::from_str("3.140").unwrap())).to_string(),
+            "3.14"
+        );
+    }
+
+    #[test]
+    fn test_display_symbol() {
+        assert_eq!(Value::Symbol("foo".into()).to_string(), ":foo");
+        assert_eq!(
+            Value::Symbol("hello world".into()).to_string(),
+            r#":"hello world""#
+        );
+        assert_eq!(Value::Symbol("v-2".into()).to_string(), r#":"v-2""#);
+    }
+
+    #[test]
+    fn test_display_list() {
+        let list = Value::from(vec![
+            Value::from(1),
+            Value::from("a \"b\""),
+            Value::from(vec![Value::Symbol("c".into())]),
+        ]);
+        assert_eq!(list.to_string(), r#"[1, "a \"b\"", [:c]]"#);
+        assert_eq!(Value::from(vec![]).to_string(), "[]");
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(Value::Symbol("hello world".into()).to_text(), "hello world");
+        assert_eq!(Value::from("a").to_text(), "a");
+        assert_eq!(
+            Value::Number(Numeric::new(BigDecimal::from_str("1.50").unwrap())).to_text(),
+            "1.5"
+        );
+        assert_eq!(Value::from(false).to_text(), "false");
+        assert_eq!(Value::from(vec![Value::from("a")]).to_text(), r#"["a"]"#);
+    }
+
+    #[test]
+    fn test_eq() {
+        use chrono::TimeZone as _;
+
+        let date = Value::Date(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+        let midnight = Value::Timestamp(Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap());
+        let later = Value::Timestamp(Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 1).unwrap());
+        assert_eq!(date, midnight);
+        assert_ne!(date, later);
+        assert_eq!(Value::from(vec![date]), Value::from(vec![midnight]));
+        assert_ne!(Value::from(1), Value::from("1"));
+        assert_ne!(Value::Null, Value::from(false));
+    }
+
+    #[test]
+    fn test_display_string_and_boolean() {
+        assert_eq!(Value::from("hi").to_string(), "hi");
+        assert_eq!(Value::from(true).to_string(), "true");
+        assert_eq!(Value::Null.to_string(), "null");
+    }
+}
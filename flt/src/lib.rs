@@ -1,6 +1,9 @@
 #[doc = include_str!("../../README.md")]
 pub mod ast;
 pub mod errors;
+pub mod eval;
 pub mod parser;
 
 pub use errors::Error;
+pub use parser::parse;
+pub use parser::parse_program;
@@ -1,14 +1,11 @@
-use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::combinator::value;
-use nom::IResult;
+use super::identifier::parse_word;
+use super::IResult;
 
-/// Parses a boolean literal: `true` or `false`.
+/// Parses a boolean literal: `true` or `false`. Either must be a whole word, so `trueish` and
+/// `false-y` are names.
 pub fn parse_boolean(input: &str) -> IResult<&str, bool> {
-    alt((
-        value(true, tag("true")),
-        value(false, tag("false")),
-    ))(input)
+    let (rest, word) = parse_word(&["true", "false"])(input)?;
+    Ok((rest, word == "true"))
 }
 
 #[cfg(test)]
@@ -29,5 +26,13 @@ mod tests {
     fn test_parse_boolean_with_remainder() {
         assert_eq!(parse_boolean("true "), Ok((" ", true)));
         assert_eq!(parse_boolean("false)"), Ok((")", false)));
+        assert_eq!(parse_boolean("true-1"), Ok(("-1", true)));
+    }
+
+    #[test]
+    fn test_parse_boolean_prefix_of_name() {
+        for input in ["trueish", "falsey", "true_", "true1", "false-y"] {
+            assert!(parse_boolean(input).is_err(), "{input}");
+        }
     }
 }
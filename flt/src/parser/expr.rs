@@ -1,121 +1,779 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::multispace0;
+use nom::combinator::cut;
 use nom::combinator::map;
+use nom::combinator::opt;
+use nom::combinator::peek;
+use nom::combinator::value;
 use nom::combinator::verify;
-use nom::multi::many0;
+use nom::error::context;
+use nom::error::ErrorKind;
+use nom::error::ParseError;
+use nom::error::VerboseError;
+use nom::error::VerboseErrorKind;
+use nom::multi::separated_list0;
+use nom::multi::separated_list1;
 use nom::sequence::delimited;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
 use nom::sequence::tuple;
-use nom::IResult;
 
-use super::function::parse_function_call;
-use super::identifier::parse_identifier;
+use super::function::CallArguments;
+use super::identifier::identifier_name;
+use super::identifier::parse_binding_name;
+use super::identifier::parse_name;
+use super::identifier::parse_quoted_identifier;
+use super::identifier::parse_word;
+use super::lexer::lex;
+use super::lexer::Token;
+use super::lexer::TokenKind;
+use super::lexer::WHITESPACE;
 use super::literal::parse_literal;
 use super::operands::parse_binary_op;
 use super::operands::parse_unary_op;
+use super::source::Source;
+use super::string::parse_interpolated_string;
+use super::string::Segment;
+use super::symbol::parse_symbol_name;
+use super::trivia::attach_comments;
+use super::trivia::attach_statement_comments;
+use super::IResult;
 use crate::ast::BinaryOp;
+use crate::ast::Comment;
 use crate::ast::Expr;
+use crate::ast::ExprKind;
+use crate::ast::Identifier;
+use crate::ast::Literal;
+use crate::ast::Statement;
+use crate::ast::StringPart;
 
-/// Parses a primary expression: literal, identifier, function call, or parenthesized expression.
-fn parse_primary(input: &str) -> IResult<&str, Expr> {
-    alt((
-        map(parse_literal, Expr::Literal),
-        map(parse_function_call(parse_or), |(name, args)| {
-            Expr::FunctionCall(name, args)
-        }),
-        map(parse_identifier, Expr::ident),
-        map(
-            delimited(
-                tuple((multispace0, tag("("), multispace0)),
-                parse_or,
-                tuple((multispace0, tag(")"), multispace0)),
-            ),
-            Expr::parenthesized,
-        ),
-    ))(input)
+/// What follows a value: the inside of `[...]`, a `.field`, a `?.field`, or an argument list.
+enum Suffix {
+    Index(Expr),
+    Slice(Option<Expr>, Option<Expr>),
+    Field(Identifier),
+    SafeField(Identifier),
+    Call(Vec<Expr>, Vec<(Identifier, Expr)>),
 }
 
-/// Parses a unary expression: optionally prefixed with `!`, `+`, or `-`.
-fn parse_unary(input: &str) -> IResult<&str, Expr> {
-    let (input, _) = multispace0(input)?;
-    alt((
-        map(
-            tuple((parse_unary_op, multispace0, parse_unary)),
-            |(op, _, e)| Expr::unary_expr(op, e),
-        ),
-        parse_primary,
-    ))(input)
+/// The classes of literal tokens.
+pub(super) const LITERALS: &[TokenKind] = &[
+    TokenKind::Number,
+    TokenKind::String,
+    TokenKind::Symbol,
+    TokenKind::Boolean,
+    TokenKind::Null,
+    TokenKind::DateTime,
+    TokenKind::Duration,
+    TokenKind::Regex,
+];
+
+/// The classes of token that can be a record key or field name: a name, reserved words
+/// included, or a string.
+const KEYS: &[TokenKind] = &[
+    TokenKind::Identifier,
+    TokenKind::Keyword,
+    TokenKind::Boolean,
+    TokenKind::Null,
+    TokenKind::Operator,
+    TokenKind::String,
+];
+
+/// How deeply expressions and patterns may nest.
+const MAX_DEPTH: usize = 256;
+
+/// The stack a level of nesting may use before the next, with room to spare: about 32 KiB in a
+/// debug build. When less than this is left, parsing continues on a newly allocated stack of
+/// [`STACK_SIZE`] bytes.
+const RED_ZONE: usize = 128 * 1024;
+const STACK_SIZE: usize = 1024 * 1024;
+
+/// Parses expressions out of a [`Source`], recording the span of every node and the
+/// comments between them.
+pub(crate) struct ExprParser<'s> {
+    source: Source<'s>,
+    /// The tokens of the source, those in the holes of interpolated strings included, in order.
+    tokens: Vec<Token<'s>>,
+    /// Comments skipped so far, by offset. Backtracking may skip the same comment twice.
+    comments: RefCell<BTreeMap<usize, &'s str>>,
+    /// How many nested expressions and patterns are being parsed.
+    depth: Cell<usize>,
+    /// Whether a line break can end the expression being parsed: in the statements of a program,
+    /// outside any brackets (see [`Self::bracketed`]).
+    statement_level: Cell<bool>,
 }
 
-/// Parses binary expressions: `Expr` then `BinaryOp` then `Expr`, with left-associative folding.
-/// `next` parses the higher-precedence operand; `allowed` restricts which operators this level accepts.
-/// Precedence (lowest to highest): ||, &&, ^^, |, ^, &, +/-, *, /
-fn parse_binary_level<'a>(
-    input: &'a str,
-    next: fn(&str) -> IResult<&str, Expr>,
-    allowed: &[BinaryOp],
-) -> IResult<&'a str, Expr> {
-    let parse_expr_binary_op_expr = tuple((
-        next,
-        many0(tuple((
-            multispace0,
-            verify(parse_binary_op, |o: &BinaryOp| allowed.contains(o)),
-            multispace0,
-            next,
-        ))),
-    ));
-    map(
-        parse_expr_binary_op_expr,
-        |(left, pairs): (Expr, Vec<(_, BinaryOp, _, Expr)>)| {
-            pairs.into_iter().fold(left, |acc, (_, op, _, right)| {
-                Expr::binary_expr(acc, op, right)
+impl<'s> ExprParser<'s> {
+    /// A parser of `source`. If only part of it splits into tokens, parsing fails where that part
+    /// ends.
+    pub fn new(source: &'s str) -> Self {
+        Self::with_tokens(source, lex(source).0)
+    }
+
+    /// A parser of `source`, which [`lex`] split into `tokens`.
+    pub(super) fn with_tokens(source: &'s str, tokens: Vec<Token<'s>>) -> Self {
+        Self {
+            source: Source::new(source),
+            tokens,
+            comments: RefCell::new(BTreeMap::new()),
+            depth: Cell::new(0),
+            statement_level: Cell::new(false),
+        }
+    }
+
+    pub fn source(&self) -> &Source<'s> {
+        &self.source
+    }
+
+    /// Skips whitespace and comment tokens, remembering the comments.
+    pub(super) fn ws(&self, input: &'s str) -> IResult<&'s str, ()> {
+        let mut rest = input.trim_start_matches(WHITESPACE);
+        while let Some(comment) = self.token(rest).filter(|t| t.kind == TokenKind::Comment) {
+            self.comments
+                .borrow_mut()
+                .insert(comment.span.start, comment.text);
+            rest = rest[comment.text.len()..].trim_start_matches(WHITESPACE);
+        }
+        Ok((rest, ()))
+    }
+
+    /// The token that starts at `input`, if any.
+    fn token(&self, input: &'s str) -> Option<&Token<'s>> {
+        let offset = self.source.offset(input);
+        let i = self
+            .tokens
+            .binary_search_by_key(&offset, |token| token.span.start)
+            .ok()?;
+        Some(&self.tokens[i])
+    }
+
+    /// Reads the token at `input` with `parser`, which must read the whole of it, if the token
+    /// is one of `kinds`. The parser's own errors are reported as they are, so that they say
+    /// what it expected.
+    pub(super) fn lexeme<'p, T>(
+        &'p self,
+        kinds: &'p [TokenKind],
+        mut parser: impl FnMut(&'s str) -> IResult<&'s str, T> + 'p,
+    ) -> impl FnMut(&'s str) -> IResult<&'s str, T> + 'p {
+        move |input| {
+            let (rest, output) = parser(input)?;
+            match self.token(input) {
+                Some(token)
+                    if kinds.contains(&token.kind)
+                        && token.span.end == self.source.offset(rest) =>
+                {
+                    Ok((rest, output))
+                }
+                _ => Err(nom::Err::Error(VerboseError::from_error_kind(
+                    input,
+                    ErrorKind::Verify,
+                ))),
+            }
+        }
+    }
+
+    /// Reads the token at `input` if it is of `kind` and reads `text`.
+    fn exact(&self, input: &'s str, kind: TokenKind, text: &str) -> Option<(&'s str, &'s str)> {
+        let token = self
+            .token(input)
+            .filter(|t| t.kind == kind && t.text == text)?;
+        Some((&input[text.len()..], token.text))
+    }
+
+    /// Parses the punctuation `text`.
+    pub(super) fn punct<'p>(
+        &'p self,
+        text: &'static str,
+    ) -> impl Fn(&'s str) -> IResult<&'s str, &'s str> + 'p {
+        move |input| {
+            self.exact(input, TokenKind::Punctuation, text)
+                .ok_or_else(|| {
+                    nom::Err::Error(VerboseError::from_error_kind(input, ErrorKind::Tag))
+                })
+        }
+    }
+
+    /// Parses the punctuation `c`, failing with an error that expects it: use this where
+    /// nothing else may follow, as `char` is used (see [`Source::error`]).
+    pub(super) fn char<'p>(&'p self, c: char) -> impl Fn(&'s str) -> IResult<&'s str, char> + 'p {
+        move |input| {
+            let mut text = [0; 4];
+            match self.exact(input, TokenKind::Punctuation, c.encode_utf8(&mut text)) {
+                Some((rest, _)) => Ok((rest, c)),
+                None => Err(nom::Err::Error(VerboseError::from_char(input, c))),
+            }
+        }
+    }
+
+    /// Parses the keyword `kw`.
+    pub(super) fn keyword<'p>(
+        &'p self,
+        kw: &'static str,
+    ) -> impl Fn(&'s str) -> IResult<&'s str, &'s str> + 'p {
+        move |input| {
+            self.exact(input, TokenKind::Keyword, kw).ok_or_else(|| {
+                nom::Err::Error(VerboseError::from_error_kind(input, ErrorKind::Tag))
             })
-        },
-    )(input)
-}
+        }
+    }
 
-fn parse_pipe(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_or, &[BinaryOp::Pipe])
-}
+    /// Parses a name being read as a variable or called as a function: an identifier token, so
+    /// never a bare reserved word. `match + 1` is not an addition: write `` `match` + 1 ``.
+    pub(super) fn parse_variable_name(&self, input: &'s str) -> IResult<&'s str, &'s str> {
+        self.lexeme(&[TokenKind::Identifier], parse_name)(input)
+    }
 
-fn parse_or(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_and, &[BinaryOp::Or])
-}
+    /// Parses a name being bound (see [`parse_binding_name`]).
+    pub(super) fn parse_binding_name(&self, input: &'s str) -> IResult<&'s str, &'s str> {
+        self.lexeme(&[TokenKind::Identifier], parse_binding_name)(input)
+    }
 
-fn parse_and(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_xor, &[BinaryOp::And])
-}
+    /// Runs `parser` one level of nesting deeper, on a fresh stack segment if the current one is
+    /// nearly used up. Past [`MAX_DEPTH`] levels it fails instead, which is reported as
+    /// "nesting too deep", so that deeply nested input cannot exhaust the stack or memory.
+    pub(super) fn nested<T>(
+        &self,
+        input: &'s str,
+        parser: impl FnOnce(&'s str) -> IResult<&'s str, T>,
+    ) -> IResult<&'s str, T> {
+        let depth = self.depth.get();
+        if depth >= MAX_DEPTH {
+            return Err(nom::Err::Failure(VerboseError {
+                errors: vec![(input, VerboseErrorKind::Nom(ErrorKind::TooLarge))],
+            }));
+        }
+        self.depth.set(depth + 1);
+        let result = stacker::maybe_grow(RED_ZONE, STACK_SIZE, || parser(input));
+        self.depth.set(depth);
+        result
+    }
 
-fn parse_xor(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_bit_or, &[BinaryOp::Xor])
-}
+    /// Parses the statements of a program from here on, where a line break can end an expression.
+    pub(super) fn start_statements(&self) {
+        self.statement_level.set(true);
+    }
 
-fn parse_bit_or(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_bit_xor, &[BinaryOp::BitOr])
-}
+    /// Runs `parser` on an enclosed part of an expression, where a line break never ends it:
+    /// inside brackets, or between `if` and `then`, `then` and `else`, or `match` and `{`.
+    fn bracketed<T>(
+        &self,
+        input: &'s str,
+        parser: impl FnOnce(&'s str) -> IResult<&'s str, T>,
+    ) -> IResult<&'s str, T> {
+        let outer = self.statement_level.replace(false);
+        let result = parser(input);
+        self.statement_level.set(outer);
+        result
+    }
 
-fn parse_bit_xor(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_bit_and, &[BinaryOp::BitXor])
-}
+    /// Parses an enclosed expression (see [`Self::bracketed`]).
+    pub(super) fn parse_inner(&self, input: &'s str) -> IResult<&'s str, Expr> {
+        self.bracketed(input, |i| self.parse_pipe(i))
+    }
+
+    /// Wraps a parser of an expression kind so that it produces an `Expr` spanning the input it consumed.
+    fn spanned<'p, F>(&'p self, mut parser: F) -> impl FnMut(&'s str) -> IResult<&'s str, Expr> + 'p
+    where
+        F: FnMut(&'s str) -> IResult<&'s str, ExprKind> + 'p,
+    {
+        move |input| {
+            let (rest, kind) = parser(input)?;
+            Ok((rest, Expr::new(kind, self.source.span(input, rest))))
+        }
+    }
+
+    /// Parses the argument list of a call of any expression, as in `(fn(x) => x)(1)`.
+    fn parse_call_suffix(&self, input: &'s str) -> IResult<&'s str, Suffix> {
+        let (rest, CallArguments { args, named }) = self.parse_arguments(input)?;
+        Ok((rest, Suffix::Call(args, named)))
+    }
+
+    /// Parses a quoted string. One with `${...}` holes is an interpolation; any other is a
+    /// string literal.
+    fn parse_interpolation(&self, input: &'s str) -> IResult<&'s str, ExprKind> {
+        let (rest, segments) =
+            parse_interpolated_string(|i| self.parse_inner(i), |i| self.ws(i))(input)?;
+        let mut parts: Vec<StringPart> = segments
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => StringPart::Text(text),
+                Segment::Hole(expr) => StringPart::Expr(expr),
+            })
+            .collect();
+        let kind = match parts.as_mut_slice() {
+            [] => ExprKind::Literal(Literal::string("")),
+            [StringPart::Text(text)] => ExprKind::Literal(Literal::string(std::mem::take(text))),
+            _ => ExprKind::Interpolation(parts),
+        };
+        Ok((rest, kind))
+    }
+
+    /// Parses a conditional: `if` Expr `then` Expr `else` Expr. Chains are written
+    /// `else if ...`, since the `else` branch is itself an expression.
+    fn parse_if(&self, input: &'s str) -> IResult<&'s str, ExprKind> {
+        let (rest, _) = self.keyword("if")(input)?;
+        let (rest, (_, cond, _, _, _, then, _, _, _, otherwise)) = cut(tuple((
+            |i| self.ws(i),
+            |i| self.parse_inner(i),
+            |i| self.ws(i),
+            context("'then'", self.keyword("then")),
+            |i| self.ws(i),
+            |i| self.parse_inner(i),
+            |i| self.ws(i),
+            context("'else'", self.keyword("else")),
+            |i| self.ws(i),
+            |i| self.parse_pipe(i),
+        )))(rest)?;
+        Ok((
+            rest,
+            ExprKind::If(Box::new(cond), Box::new(then), Box::new(otherwise)),
+        ))
+    }
+
+    /// Parses a match: `match` Expr `{` (Pattern `=>` Expr)+ `}`, with comma-separated arms.
+    fn parse_match(&self, input: &'s str) -> IResult<&'s str, ExprKind> {
+        let (rest, (_, _, value, _, _)) = tuple((
+            self.keyword("match"),
+            |i| self.ws(i),
+            |i| self.parse_inner(i),
+            |i| self.ws(i),
+            self.punct("{"),
+        ))(input)?;
+        let arm = |input: &'s str| {
+            let (rest, pattern) = self.parse_pattern(input)?;
+            let (rest, (_, _, _, body)) = cut(tuple((
+                |i| self.ws(i),
+                context("'=>'", self.punct("=>")),
+                |i| self.ws(i),
+                |i| self.parse_inner(i),
+            )))(rest)?;
+            Ok((rest, (pattern, body)))
+        };
+        let (rest, arms) = cut(terminated(
+            delimited(
+                |i| self.ws(i),
+                terminated(
+                    separated_list1(tuple((|i| self.ws(i), self.char(','), |i| self.ws(i))), arm),
+                    opt(tuple((|i| self.ws(i), self.char(',')))),
+                ),
+                |i| self.ws(i),
+            ),
+            self.char('}'),
+        ))(rest)?;
+        Ok((rest, ExprKind::Match(Box::new(value), arms)))
+    }
+
+    /// Parses a record: `{` (key `:` Expr)* `}`, with comma-separated fields and unique keys.
+    fn parse_record(&self, input: &'s str) -> IResult<&'s str, ExprKind> {
+        let field = |input: &'s str| {
+            let (rest, key) = self.parse_key(input)?;
+            let (rest, (_, _, _, value)) = cut(tuple((
+                |i| self.ws(i),
+                self.char(':'),
+                |i| self.ws(i),
+                |i| self.parse_inner(i),
+            )))(rest)?;
+            Ok((rest, (input, key, value)))
+        };
+        let (rest, fields) = preceded(
+            self.punct("{"),
+            cut(terminated(
+                delimited(
+                    |i| self.ws(i),
+                    separated_list0(
+                        tuple((|i| self.ws(i), self.char(','), |i| self.ws(i))),
+                        field,
+                    ),
+                    |i| self.ws(i),
+                ),
+                self.char('}'),
+            )),
+        )(input)?;
+
+        let mut record: Vec<(Identifier, Expr)> = vec![];
+        for (at, key, value) in fields {
+            if record.iter().any(|(k, _)| k.name == key.name) {
+                return Err(nom::Err::Failure(VerboseError {
+                    errors: vec![(at, VerboseErrorKind::Context("unique field name"))],
+                }));
+            }
+            record.push((key, value));
+        }
+        Ok((rest, ExprKind::Record(record)))
+    }
+
+    /// Parses a record key or field name: a symbol name (`name` or `"any text"`) or a quoted
+    /// identifier (`` `any text` ``). A bare reserved word is a key too, as in `{if: 1}`.
+    pub(super) fn parse_key(&self, input: &'s str) -> IResult<&'s str, Identifier> {
+        let (rest, name) = self.lexeme(
+            KEYS,
+            alt((parse_symbol_name, map(parse_quoted_identifier, Cow::Owned))),
+        )(input)?;
+        let span = self.source.span(input, rest);
+        Ok((
+            rest,
+            Identifier {
+                name: name.into_owned(),
+                span,
+            },
+        ))
+    }
 
-fn parse_bit_and(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_add_sub, &[BinaryOp::BitAnd])
+    /// Constructs an identifier from `name`, a slice of the source holding a bare or quoted name.
+    pub(super) fn identifier(&self, name: &'s str) -> Identifier {
+        Identifier {
+            name: identifier_name(name).into_owned(),
+            span: self.source.span_of(name),
+        }
+    }
+
+    /// Parses a primary expression: string, literal, conditional, match, lambda, identifier,
+    /// function call, list, record, or parenthesized expression.
+    fn parse_primary(&self, input: &'s str) -> IResult<&'s str, Expr> {
+        context(
+            "expression",
+            alt((
+                self.spanned(self.lexeme(&[TokenKind::String], |i| self.parse_interpolation(i))),
+                self.spanned(map(self.lexeme(LITERALS, parse_literal), ExprKind::Literal)),
+                self.spanned(|i| self.parse_if(i)),
+                self.spanned(|i| self.parse_match(i)),
+                self.spanned(|i| self.parse_lambda(i)),
+                self.spanned(|i| self.parse_function_call(i)),
+                self.spanned(map(
+                    |i| self.parse_variable_name(i),
+                    |s: &str| ExprKind::Ident(identifier_name(s).into_owned()),
+                )),
+                self.spanned(map(
+                    preceded(
+                        self.punct("("),
+                        cut(terminated(
+                            delimited(|i| self.ws(i), |i| self.parse_inner(i), |i| self.ws(i)),
+                            self.char(')'),
+                        )),
+                    ),
+                    |e| ExprKind::Parenthesized(Box::new(e)),
+                )),
+                self.spanned(map(
+                    preceded(
+                        self.punct("["),
+                        cut(terminated(
+                            delimited(
+                                |i| self.ws(i),
+                                separated_list0(
+                                    tuple((|i| self.ws(i), self.char(','), |i| self.ws(i))),
+                                    |i| self.parse_inner(i),
+                                ),
+                                |i| self.ws(i),
+                            ),
+                            self.char(']'),
+                        )),
+                    ),
+                    ExprKind::List,
+                )),
+                self.spanned(|i| self.parse_record(i)),
+            )),
+        )(input)
+    }
+
+    /// Parses a primary expression followed by any number of index (`xs[i]`), slice (`xs[a:b]`),
+    /// field (`rec.name`), null-safe field (`rec?.name`) or call (`f(1)(2)`) suffixes. A suffix
+    /// must follow immediately, so a list or parenthesized expression at the start of the next
+    /// line is not an index or a call, and `..` starts a range rather than a field.
+    fn parse_postfix(&self, input: &'s str) -> IResult<&'s str, Expr> {
+        let (mut rest, mut expr) = self.parse_primary(input)?;
+        loop {
+            let (after, suffix) = opt(alt((
+                preceded(
+                    self.punct("["),
+                    cut(terminated(
+                        |i| self.bracketed(i, |i| self.parse_subscript(i)),
+                        self.char(']'),
+                    )),
+                ),
+                preceded(
+                    self.punct("."),
+                    cut(map(
+                        context("field name", |i| self.parse_key(i)),
+                        Suffix::Field,
+                    )),
+                ),
+                preceded(
+                    self.punct("?."),
+                    cut(map(
+                        context("field name", |i| self.parse_key(i)),
+                        Suffix::SafeField,
+                    )),
+                ),
+                |i| self.parse_call_suffix(i),
+            )))(rest)?;
+            let Some(suffix) = suffix else {
+                return Ok((rest, expr));
+            };
+            let target = Box::new(expr);
+            let kind = match suffix {
+                Suffix::Index(index) => ExprKind::Index(target, Box::new(index)),
+                Suffix::Slice(start, end) => {
+                    ExprKind::Slice(target, start.map(Box::new), end.map(Box::new))
+                }
+                Suffix::Field(name) => ExprKind::Field(target, name),
+                Suffix::SafeField(name) => ExprKind::SafeField(target, name),
+                Suffix::Call(args, named) => ExprKind::Call(target, args, named),
+            };
+            expr = Expr::new(kind, self.source.span(input, after));
+            rest = after;
+        }
+    }
+
+    /// Parses the inside of `[...]`: an index, or a slice `start:end` with either bound optional.
+    /// A leading `:` always starts a slice, so `xs[:2]` is the first two items, not `xs` indexed by `:2`.
+    fn parse_subscript(&self, input: &'s str) -> IResult<&'s str, Suffix> {
+        let (input, _) = self.ws(input)?;
+        let (input, start) = if peek(self.punct(":"))(input).is_ok() {
+            (input, None)
+        } else {
+            let (input, start) = self.parse_pipe(input)?;
+            (input, Some(start))
+        };
+        let (input, _) = self.ws(input)?;
+        let (input, colon) = opt(self.punct(":"))(input)?;
+        match (start, colon) {
+            (Some(index), None) => Ok((input, Suffix::Index(index))),
+            (start, _) => {
+                let (input, _) = self.ws(input)?;
+                let (input, end) = opt(|i| self.parse_pipe(i))(input)?;
+                let (input, _) = self.ws(input)?;
+                Ok((input, Suffix::Slice(start, end)))
+            }
+        }
+    }
+
+    /// Parses a unary expression: optionally prefixed with `!`, `+`, or `-`.
+    /// Every nested expression is parsed through here, so this is where nesting is counted.
+    fn parse_unary(&self, input: &'s str) -> IResult<&'s str, Expr> {
+        let (input, _) = self.ws(input)?;
+        self.nested(input, |input| {
+            alt((
+                self.spanned(map(
+                    tuple((
+                        self.lexeme(&[TokenKind::Operator], parse_unary_op),
+                        |i| self.ws(i),
+                        |i| self.parse_unary(i),
+                    )),
+                    |(op, _, e)| ExprKind::UnaryExpr(op, Box::new(e)),
+                )),
+                |i| self.parse_power(i),
+            ))(input)
+        })
+    }
+
+    /// Parses exponentiation: a postfix expression optionally followed by `**` and a unary expression.
+    /// `**` is right-associative and binds tighter than a unary operator on its left,
+    /// so `-2 ** 2` is `-(2 ** 2)` while `2 ** -1` is `2 ** (-1)`.
+    fn parse_power(&self, input: &'s str) -> IResult<&'s str, Expr> {
+        let (input, base) = self.parse_postfix(input)?;
+        let (input, exponent) = opt(preceded(
+            tuple((
+                |i| self.ws(i),
+                verify(
+                    self.lexeme(&[TokenKind::Operator], parse_binary_op),
+                    |o: &BinaryOp| *o == BinaryOp::Pow,
+                ),
+                |i| self.ws(i),
+            )),
+            cut(|i| self.parse_unary(i)),
+        ))(input)?;
+        Ok((
+            input,
+            match exponent {
+                Some(exponent) => {
+                    let span = base.span.to(exponent.span);
+                    Expr::binary_expr(base, BinaryOp::Pow, exponent).with_span(span)
+                }
+                None => base,
+            },
+        ))
+    }
+
+    /// Parses the binary operators and ranges of precedence `min` and above, by precedence
+    /// climbing: an operand, then any number of operators each followed by the operand on their
+    /// right, which takes every operator that binds tighter. Binary operators are
+    /// left-associative. Once an operator has been consumed, a missing right operand is a failure
+    /// rather than a backtrack.
+    ///
+    /// Precedence (lowest to highest): |>, ??, ||, &&, ^^, ==/!=/=~/!~, </<=/>/>=/in, ranges,
+    /// |, ^, &, +/-, * / // %, then unary operators, then ** (see [`Self::parse_power`]).
+    /// The levels are a loop rather than a function each, so that a nested expression takes
+    /// few stack frames.
+    fn parse_binary(&self, input: &'s str, min: usize) -> IResult<&'s str, Expr> {
+        let (mut rest, mut left) = self.parse_unary(input)?;
+        let mut ranged = false;
+        loop {
+            let (next, _) = self.ws(rest)?;
+            let Ok((after, infix)) = self.parse_infix(next) else {
+                return Ok((rest, left));
+            };
+            // A statement's line ending before `-` or `+` ends the statement: `let a = 5` then
+            // `-a` on the next line are two statements, not `5 - a`.
+            let line_break = rest[..rest.len() - next.len()].contains('\n');
+            if line_break
+                && self.statement_level.get()
+                && matches!(infix, Infix::Binary(BinaryOp::Sub | BinaryOp::Add))
+            {
+                return Ok((rest, left));
+            }
+            let level = infix.level();
+            // Ranges do not chain: the second `..` of `a..b..c` is left unparsed.
+            if level < min || ranged && matches!(infix, Infix::Range(_)) {
+                return Ok((rest, left));
+            }
+            let (after, _) = self.ws(after)?;
+            let (after, right) = cut(|i| self.parse_binary(i, level + 1))(after)?;
+            (rest, left) = match infix {
+                Infix::Binary(op) => {
+                    let span = left.span.to(right.span);
+                    (after, Expr::binary_expr(left, op, right).with_span(span))
+                }
+                Infix::Range(inclusive) => {
+                    ranged = true;
+                    self.parse_range(after, left, right, inclusive)?
+                }
+            };
+        }
+    }
+
+    /// Completes a range `start..end` or `start..=end` with its optional `step`, as in
+    /// `0..100 step 10`. Its bounds bind tighter than comparisons but looser than arithmetic,
+    /// so `x in 0..n + 1` is `x in (0..(n + 1))`.
+    ///
+    /// `step` is a keyword only here, so it can name a variable elsewhere. In a statement, it
+    /// must be on the range's line: `let r = 0..10` then `step(2)` on the next line are two
+    /// statements.
+    fn parse_range(
+        &self,
+        input: &'s str,
+        start: Expr,
+        end: Expr,
+        inclusive: bool,
+    ) -> IResult<&'s str, Expr> {
+        let (next, _) = self.ws(input)?;
+        let line_break = input[..input.len() - next.len()].contains('\n');
+        let step = preceded(
+            tuple((
+                self.lexeme(&[TokenKind::Identifier], parse_word(&["step"])),
+                |i| self.ws(i),
+            )),
+            cut(|i| self.parse_binary(i, Infix::Range(inclusive).level() + 1)),
+        );
+        let (rest, step) = if line_break && self.statement_level.get() {
+            (input, None)
+        } else {
+            match opt(step)(next)? {
+                (rest, Some(step)) => (rest, Some(step)),
+                (_, None) => (input, None),
+            }
+        };
+        let span = start.span.to(step.as_ref().unwrap_or(&end).span);
+        Ok((
+            rest,
+            Expr::range(start, end, inclusive, step).with_span(span),
+        ))
+    }
+
+    /// Parses an infix operator other than `**`, which [`Self::parse_power`] handles.
+    fn parse_infix(&self, input: &'s str) -> IResult<&'s str, Infix> {
+        alt((
+            value(Infix::Range(true), self.punct("..=")),
+            value(Infix::Range(false), self.punct("..")),
+            map(
+                verify(
+                    self.lexeme(&[TokenKind::Operator], parse_binary_op),
+                    |o: &BinaryOp| *o != BinaryOp::Pow,
+                ),
+                Infix::Binary,
+            ),
+        ))(input)
+    }
+
+    pub(super) fn parse_pipe(&self, input: &'s str) -> IResult<&'s str, Expr> {
+        self.parse_binary(input, 0)
+    }
+
+    /// Parses an expression: unary and binary with proper precedence.
+    /// Comments anywhere within or around it are attached to its nodes as trivia.
+    pub fn parse_expr(&self, input: &'s str) -> IResult<&'s str, Expr> {
+        let (input, _) = self.ws(input)?;
+        let (input, mut expr) = self.parse_pipe(input)?;
+        let (input, _) = self.ws(input)?;
+        self.attach_comments(&mut [&mut expr], input);
+        Ok((input, expr))
+    }
+
+    /// Attaches the comments skipped before `rest` to the nodes of `roots`.
+    fn attach_comments(&self, roots: &mut [&mut Expr], rest: &'s str) {
+        attach_comments(roots, self.take_comments(rest), self.source.text());
+    }
+
+    /// Attaches the comments skipped before `rest` to `statements` and their expressions.
+    pub(super) fn attach_statement_comments(&self, statements: &mut [Statement], rest: &'s str) {
+        let comments = self.take_comments(rest);
+        attach_statement_comments(statements, comments, self.source.text());
+    }
+
+    /// Takes the comments skipped before `rest`.
+    fn take_comments(&self, rest: &'s str) -> Vec<Comment> {
+        let end = self.source.offset(rest);
+        self.comments
+            .take()
+            .into_iter()
+            .filter(|(start, _)| *start < end)
+            .map(|(_, text)| Comment {
+                text: text[1..].to_string(),
+                span: self.source.span_of(text),
+            })
+            .collect()
+    }
 }
 
-fn parse_add_sub(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_mul_div, &[BinaryOp::Add, BinaryOp::Sub])
+/// An operator between two operands: a binary operator, or the `..` (`..=` if inclusive) of a range.
+#[derive(Clone, Copy)]
+enum Infix {
+    Binary(BinaryOp),
+    Range(bool),
 }
 
-fn parse_mul_div(input: &str) -> IResult<&str, Expr> {
-    parse_binary_level(input, parse_unary, &[BinaryOp::Mul, BinaryOp::Div])
+impl Infix {
+    /// The precedence level of the operator, from 0 for `|>` up. `**` is parsed with unary
+    /// operators instead (see [`ExprParser::parse_power`]), above every level here.
+    fn level(self) -> usize {
+        match self {
+            Infix::Binary(BinaryOp::Pipe) => 0,
+            Infix::Binary(BinaryOp::Coalesce) => 1,
+            Infix::Binary(BinaryOp::Or) => 2,
+            Infix::Binary(BinaryOp::And) => 3,
+            Infix::Binary(BinaryOp::Xor) => 4,
+            Infix::Binary(BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Match | BinaryOp::NotMatch) => 5,
+            Infix::Binary(
+                BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge | BinaryOp::In,
+            ) => 6,
+            Infix::Range(_) => 7,
+            Infix::Binary(BinaryOp::BitOr) => 8,
+            Infix::Binary(BinaryOp::BitXor) => 9,
+            Infix::Binary(BinaryOp::BitAnd) => 10,
+            Infix::Binary(BinaryOp::Add | BinaryOp::Sub) => 11,
+            Infix::Binary(BinaryOp::Mul | BinaryOp::Div | BinaryOp::IntDiv | BinaryOp::Rem) => 12,
+            Infix::Binary(BinaryOp::Pow) => 13,
+        }
+    }
 }
 
 /// Parses an expression: unary and binary with proper precedence.
+/// Spans of the resulting nodes are relative to `input`.
 pub fn parse_expr(input: &str) -> IResult<&str, Expr> {
-    let (input, _) = multispace0(input)?;
-    let (input, expr) = parse_pipe(input)?;
-    let (input, _) = multispace0(input)?;
-    Ok((input, expr))
+    ExprParser::new(input).parse_expr(input)
 }
 
 #[cfg(test)]
@@ -126,10 +784,24 @@ mod tests {
 
     use crate::ast::BinaryOp;
     use crate::ast::Expr;
+    use crate::ast::ExprKind;
+    use crate::ast::Literal;
+    use crate::ast::Pattern;
+    use crate::ast::Span;
+    use crate::ast::Trivia;
     use crate::ast::UnaryOp;
 
     use super::*;
 
+    /// `expr` with the given leading and trailing comments.
+    fn commented(expr: Expr, leading: &[&str], trailing: &[&str]) -> Expr {
+        let comments = |texts: &[&str]| texts.iter().map(|&text| Comment::new(text)).collect();
+        expr.with_trivia(Trivia {
+            leading: comments(leading),
+            trailing: comments(trailing),
+        })
+    }
+
     #[test]
     fn test_parse_number() {
         assert_eq!(parse_expr("42"), Ok(("", Expr::literal_number(42))));
@@ -147,6 +819,38 @@ mod tests {
     #[test]
     fn test_parse_identifier() {
         assert_eq!(parse_expr("foo"), Ok(("", Expr::ident("foo"))));
+        assert_eq!(parse_expr("größe"), Ok(("", Expr::ident("größe"))));
+    }
+
+    #[test]
+    fn test_parse_quoted_identifier() {
+        assert_eq!(
+            parse_expr("`order id` + `if`"),
+            Ok((
+                "",
+                Expr::binary_expr(Expr::ident("order id"), BinaryOp::Add, Expr::ident("if"))
+            ))
+        );
+        let name = |name: &str| Identifier {
+            name: name.to_string(),
+            span: Span::default(),
+        };
+        assert_eq!(
+            parse_expr("`round up`(x, `decimal places`: 2)"),
+            Ok((
+                "",
+                ExprKind::FunctionCall(
+                    name("round up"),
+                    vec![Expr::ident("x")],
+                    vec![(name("decimal places"), Expr::literal_number(2))]
+                )
+                .into()
+            ))
+        );
+        assert!(matches!(
+            parse_expr("fn(a, `a`) => a"),
+            Err(nom::Err::Failure(_))
+        ));
     }
 
     #[test]
@@ -231,29 +935,147 @@ mod tests {
             parse_expr("foo()"),
             Ok((
                 "",
-                Expr::FunctionCall(
+                Expr::from(ExprKind::FunctionCall(
                     Identifier::try_from("foo").expect("invalid identifier"),
+                    vec![],
                     vec![]
-                )
+                ))
             ))
         );
         assert_eq!(
             parse_expr("bar(1)"),
             Ok((
                 "",
-                Expr::FunctionCall(
+                Expr::from(ExprKind::FunctionCall(
                     Identifier::try_from("bar").expect("invalid identifier"),
-                    vec![Expr::literal_number(1)]
-                )
+                    vec![Expr::literal_number(1)],
+                    vec![]
+                ))
             ))
         );
         assert_eq!(
             parse_expr("add(1, 2)"),
             Ok((
                 "",
-                Expr::FunctionCall(
+                Expr::from(ExprKind::FunctionCall(
                     Identifier::try_from("add").expect("invalid identifier"),
-                    vec![Expr::literal_number(1), Expr::literal_number(2)]
+                    vec![Expr::literal_number(1), Expr::literal_number(2)],
+                    vec![]
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_function_call_with_named_arguments() {
+        assert_eq!(
+            parse_expr(r#"WRITE("test.json", pretty: true)"#),
+            Ok((
+                "",
+                Expr::function_call_named(
+                    "WRITE",
+                    vec![Expr::literal_string("test.json")],
+                    vec![("pretty", Expr::literal_boolean(true))]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("SELECT(:id, by: :name, limit:10)"),
+            Ok((
+                "",
+                Expr::function_call_named(
+                    "SELECT",
+                    vec![Expr::literal_symbol("id")],
+                    vec![
+                        ("by", Expr::literal_symbol("name")),
+                        ("limit", Expr::literal_number(10))
+                    ]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_power() {
+        // `**` binds tighter than unary minus and is right-associative.
+        assert_eq!(
+            parse_expr("-2 ** 3 ** -1"),
+            Ok((
+                "",
+                Expr::unary_expr(
+                    UnaryOp::Minus,
+                    Expr::binary_expr(
+                        Expr::literal_number(2),
+                        BinaryOp::Pow,
+                        Expr::binary_expr(
+                            Expr::literal_number(3),
+                            BinaryOp::Pow,
+                            Expr::unary_expr(UnaryOp::Minus, Expr::literal_number(1))
+                        )
+                    )
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("a // 2 % 3 * b ** 2"),
+            Ok((
+                "",
+                Expr::binary_expr(
+                    Expr::binary_expr(
+                        Expr::binary_expr(
+                            Expr::ident("a"),
+                            BinaryOp::IntDiv,
+                            Expr::literal_number(2)
+                        ),
+                        BinaryOp::Rem,
+                        Expr::literal_number(3)
+                    ),
+                    BinaryOp::Mul,
+                    Expr::binary_expr(Expr::ident("b"), BinaryOp::Pow, Expr::literal_number(2))
+                )
+            ))
+        );
+        assert!(matches!(parse_expr("2 **"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_comparison_precedence() {
+        // Comparisons bind looser than arithmetic and bitwise operators, equality looser still.
+        assert_eq!(
+            parse_expr("a + 1 < b | 2 == true && c"),
+            Ok((
+                "",
+                Expr::binary_expr(
+                    Expr::binary_expr(
+                        Expr::binary_expr(
+                            Expr::binary_expr(
+                                Expr::ident("a"),
+                                BinaryOp::Add,
+                                Expr::literal_number(1)
+                            ),
+                            BinaryOp::Lt,
+                            Expr::binary_expr(
+                                Expr::ident("b"),
+                                BinaryOp::BitOr,
+                                Expr::literal_number(2)
+                            )
+                        ),
+                        BinaryOp::Eq,
+                        Expr::literal_boolean(true)
+                    ),
+                    BinaryOp::And,
+                    Expr::ident("c")
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("x != -1"),
+            Ok((
+                "",
+                Expr::binary_expr(
+                    Expr::ident("x"),
+                    BinaryOp::Ne,
+                    Expr::unary_expr(UnaryOp::Minus, Expr::literal_number(1))
                 )
             ))
         );
@@ -344,4 +1166,624 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn test_parse_nested_pipelines() {
+        let pipe = |l, r| Expr::binary_expr(l, BinaryOp::Pipe, r);
+        let read = |path: &str| Expr::function_call("READ", vec![Expr::literal_string(path)]);
+        let head = |n: i64| Expr::function_call("HEAD", vec![Expr::literal_number(n)]);
+        assert_eq!(
+            parse_expr(r#"(READ("a.csv") |> HEAD(5))"#),
+            Ok(("", Expr::parenthesized(pipe(read("a.csv"), head(5)))))
+        );
+        assert_eq!(
+            parse_expr(r#"UNION(READ("a") |> HEAD(1), READ("b")) |> HEAD(2)"#),
+            Ok((
+                "",
+                pipe(
+                    Expr::function_call("UNION", vec![pipe(read("a"), head(1)), read("b")]),
+                    head(2)
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr(r#"JOIN(READ("a"), on: READ("b") |> HEAD(1))"#),
+            Ok((
+                "",
+                Expr::function_call_named(
+                    "JOIN",
+                    vec![read("a")],
+                    vec![("on", pipe(read("b"), head(1)))]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("[x |> f, {a: y |> g}][0 |> h]"),
+            Ok((
+                "",
+                Expr::index(
+                    Expr::list(vec![
+                        pipe(Expr::ident("x"), Expr::ident("f")),
+                        Expr::record(vec![("a", pipe(Expr::ident("y"), Expr::ident("g")))]),
+                    ]),
+                    pipe(Expr::literal_number(0), Expr::ident("h"))
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_lambda() {
+        assert_eq!(
+            parse_expr("rows |> map(fn(r) => r |> trim, 1)"),
+            Ok((
+                "",
+                Expr::binary_expr(
+                    Expr::ident("rows"),
+                    BinaryOp::Pipe,
+                    Expr::function_call(
+                        "map",
+                        vec![
+                            Expr::lambda(
+                                vec!["r"],
+                                Expr::binary_expr(
+                                    Expr::ident("r"),
+                                    BinaryOp::Pipe,
+                                    Expr::ident("trim")
+                                )
+                            ),
+                            Expr::literal_number(1)
+                        ]
+                    )
+                )
+            ))
+        );
+        let (_, expr) = parse_expr("fn(a,\n  b) => a").unwrap();
+        let ExprKind::Lambda(params, _) = &expr.kind else {
+            panic!("expected a lambda, got {:?}", expr);
+        };
+        assert_eq!((params[1].span.line, params[1].span.column), (2, 3));
+        assert_eq!(
+            parse_expr("fnord(1)"),
+            Ok((
+                "",
+                Expr::function_call("fnord", vec![Expr::literal_number(1)])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_call_of_expression() {
+        assert_eq!(
+            parse_expr("(fn(x) => x)(1)"),
+            Ok((
+                "",
+                Expr::call(
+                    Expr::parenthesized(Expr::lambda(vec!["x"], Expr::ident("x"))),
+                    vec![Expr::literal_number(1)]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("f(1)(2)"),
+            Ok((
+                "",
+                Expr::call(
+                    Expr::function_call("f", vec![Expr::literal_number(1)]),
+                    vec![Expr::literal_number(2)]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("rec.f(x: 1)"),
+            Ok((
+                "",
+                Expr::from(ExprKind::Call(
+                    Box::new(Expr::field(Expr::ident("rec"), "f")),
+                    vec![],
+                    vec![(Identifier::try_from("x").unwrap(), Expr::literal_number(1))]
+                ))
+            ))
+        );
+        // The argument list must follow immediately.
+        assert_eq!(
+            parse_expr("(f) (1)"),
+            Ok(("(1)", Expr::parenthesized(Expr::ident("f"))))
+        );
+    }
+
+    #[test]
+    fn test_parse_interpolation() {
+        assert_eq!(
+            parse_expr(r#""out/${name |> lower}.json""#),
+            Ok((
+                "",
+                Expr::interpolation(vec![
+                    StringPart::Text("out/".to_string()),
+                    StringPart::Expr(Expr::binary_expr(
+                        Expr::ident("name"),
+                        BinaryOp::Pipe,
+                        Expr::ident("lower")
+                    )),
+                    StringPart::Text(".json".to_string()),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_expr(r#""no holes, $5""#),
+            Ok(("", Expr::literal_string("no holes, $5")))
+        );
+        let input = r#""a ${x + "${y}"}""#;
+        let (_, expr) = parse_expr(input).unwrap();
+        let ExprKind::Interpolation(parts) = &expr.kind else {
+            panic!("expected an interpolation, got {:?}", expr);
+        };
+        let StringPart::Expr(hole) = &parts[1] else {
+            panic!("expected a hole, got {:?}", parts[1]);
+        };
+        assert_eq!((hole.span.start, hole.span.end), (5, 15));
+    }
+
+    #[test]
+    fn test_parse_if() {
+        assert_eq!(
+            parse_expr("if amount > 100 then :gold else if amount > 10 then :silver else :std"),
+            Ok((
+                "",
+                Expr::if_else(
+                    Expr::binary_expr(
+                        Expr::ident("amount"),
+                        BinaryOp::Gt,
+                        Expr::literal_number(100)
+                    ),
+                    Expr::literal_symbol("gold"),
+                    Expr::if_else(
+                        Expr::binary_expr(
+                            Expr::ident("amount"),
+                            BinaryOp::Gt,
+                            Expr::literal_number(10)
+                        ),
+                        Expr::literal_symbol("silver"),
+                        Expr::literal_symbol("std")
+                    )
+                )
+            ))
+        );
+        assert_eq!(parse_expr("iffy"), Ok(("", Expr::ident("iffy"))));
+        let input = "if x then 1";
+        let Err(nom::Err::Failure(e)) = parse_expr(input) else {
+            panic!("a missing `else` should fail");
+        };
+        assert!(e
+            .errors
+            .contains(&("", VerboseErrorKind::Context("'else'"))));
+    }
+
+    #[test]
+    fn test_parse_match() {
+        assert_eq!(
+            parse_expr("match status {\n  :ok => 1,\n  [first, ..] => first,\n  _ => 0,\n}"),
+            Ok((
+                "",
+                Expr::match_arms(
+                    Expr::ident("status"),
+                    vec![
+                        (
+                            Pattern::Literal(Literal::symbol("ok")),
+                            Expr::literal_number(1)
+                        ),
+                        (
+                            Pattern::List(
+                                vec![Pattern::binding("first")],
+                                Some(Box::new(Pattern::Wildcard))
+                            ),
+                            Expr::ident("first")
+                        ),
+                        (Pattern::Wildcard, Expr::literal_number(0)),
+                    ]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("`match` + 1"),
+            Ok((
+                "",
+                Expr::binary_expr(Expr::ident("match"), BinaryOp::Add, Expr::literal_number(1))
+            ))
+        );
+        assert!(parse_expr("match + 1").is_err());
+        let input = "match x { 1 2 }";
+        let Err(nom::Err::Failure(e)) = parse_expr(input) else {
+            panic!("an arm without `=>` should fail");
+        };
+        assert!(e
+            .errors
+            .contains(&(&input[12..], VerboseErrorKind::Context("'=>'"))));
+        assert!(matches!(
+            parse_expr("match x {}"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            parse_expr(r#"SELECT(["two", "four"])"#),
+            Ok((
+                "",
+                Expr::function_call(
+                    "SELECT",
+                    vec![Expr::list(vec![
+                        Expr::literal_string("two"),
+                        Expr::literal_string("four")
+                    ])]
+                )
+            ))
+        );
+        assert_eq!(parse_expr("[ ]"), Ok(("", Expr::list(vec![]))));
+        assert_eq!(
+            parse_expr("[[1], 2 + 3]"),
+            Ok((
+                "",
+                Expr::list(vec![
+                    Expr::list(vec![Expr::literal_number(1)]),
+                    Expr::binary_expr(
+                        Expr::literal_number(2),
+                        BinaryOp::Add,
+                        Expr::literal_number(3)
+                    )
+                ])
+            ))
+        );
+        assert!(matches!(parse_expr("[1, 2"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_index_and_slice() {
+        assert_eq!(
+            parse_expr("-xs[-1] ** 2"),
+            Ok((
+                "",
+                Expr::unary_expr(
+                    UnaryOp::Minus,
+                    Expr::binary_expr(
+                        Expr::index(
+                            Expr::ident("xs"),
+                            Expr::unary_expr(UnaryOp::Minus, Expr::literal_number(1))
+                        ),
+                        BinaryOp::Pow,
+                        Expr::literal_number(2)
+                    )
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("xs[1:n][0]"),
+            Ok((
+                "",
+                Expr::index(
+                    Expr::slice(
+                        Expr::ident("xs"),
+                        Some(Expr::literal_number(1)),
+                        Some(Expr::ident("n"))
+                    ),
+                    Expr::literal_number(0)
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("xs[:2]"),
+            Ok((
+                "",
+                Expr::slice(Expr::ident("xs"), None, Some(Expr::literal_number(2)))
+            ))
+        );
+        assert_eq!(
+            parse_expr("xs[ 1 : ]"),
+            Ok((
+                "",
+                Expr::slice(Expr::ident("xs"), Some(Expr::literal_number(1)), None)
+            ))
+        );
+        assert_eq!(
+            parse_expr("xs[:]"),
+            Ok(("", Expr::slice(Expr::ident("xs"), None, None)))
+        );
+        assert_eq!(parse_expr("xs [0]"), Ok(("[0]", Expr::ident("xs"))));
+        assert!(matches!(parse_expr("xs[]"), Err(nom::Err::Failure(_))));
+
+        let (_, expr) = parse_expr("f(x)[2]").unwrap();
+        assert_eq!((expr.span.start, expr.span.end), (0, 7));
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_expr("1..n"),
+            Ok((
+                "",
+                Expr::range(Expr::literal_number(1), Expr::ident("n"), false, None)
+            ))
+        );
+        assert_eq!(
+            parse_expr("x in 0 ..= n - 1 step 2"),
+            Ok((
+                "",
+                Expr::binary_expr(
+                    Expr::ident("x"),
+                    BinaryOp::In,
+                    Expr::range(
+                        Expr::literal_number(0),
+                        Expr::binary_expr(Expr::ident("n"), BinaryOp::Sub, Expr::literal_number(1)),
+                        true,
+                        Some(Expr::literal_number(2))
+                    )
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("xs[a.b..-1]"),
+            Ok((
+                "",
+                Expr::index(
+                    Expr::ident("xs"),
+                    Expr::range(
+                        Expr::field(Expr::ident("a"), "b"),
+                        Expr::unary_expr(UnaryOp::Minus, Expr::literal_number(1)),
+                        false,
+                        None
+                    )
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("0..step"),
+            Ok((
+                "",
+                Expr::range(Expr::literal_number(0), Expr::ident("step"), false, None)
+            ))
+        );
+        assert_eq!(parse_expr("index"), Ok(("", Expr::ident("index"))));
+
+        let (_, expr) = parse_expr("  1..10 step 3 ").unwrap();
+        assert_eq!((expr.span.start, expr.span.end), (2, 14));
+        assert!(matches!(parse_expr("1.."), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse_expr("0..9 step"), Err(nom::Err::Failure(_))));
+        assert_eq!(parse_expr("1..2..3").map(|(rest, _)| rest), Ok("..3"));
+    }
+
+    #[test]
+    fn test_parse_record() {
+        assert_eq!(
+            parse_expr(r#"{name: "x", "order id": 3, nested: {ok: true}}"#),
+            Ok((
+                "",
+                Expr::record(vec![
+                    ("name", Expr::literal_string("x")),
+                    ("order id", Expr::literal_number(3)),
+                    (
+                        "nested",
+                        Expr::record(vec![("ok", Expr::literal_boolean(true))])
+                    ),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_expr(r"{`order id`: 1, `a\`b`: 2}"),
+            Ok((
+                "",
+                Expr::record(vec![
+                    ("order id", Expr::literal_number(1)),
+                    ("a`b", Expr::literal_number(2)),
+                ])
+            ))
+        );
+        assert_eq!(parse_expr("{ }"), Ok(("", Expr::record(vec![]))));
+        assert!(matches!(parse_expr("{a 1}"), Err(nom::Err::Failure(_))));
+
+        let input = "{a: 1, a: 2}";
+        let Err(nom::Err::Failure(e)) = parse_expr(input) else {
+            panic!("duplicate keys should fail");
+        };
+        assert_eq!(
+            e.errors[0],
+            (&input[7..], VerboseErrorKind::Context("unique field name"))
+        );
+    }
+
+    #[test]
+    fn test_parse_null_coalescing() {
+        assert_eq!(
+            parse_expr("row?.price ?? 0 || done |> f"),
+            Ok((
+                "",
+                Expr::binary_expr(
+                    Expr::binary_expr(
+                        Expr::safe_field(Expr::ident("row"), "price"),
+                        BinaryOp::Coalesce,
+                        Expr::binary_expr(
+                            Expr::literal_number(0),
+                            BinaryOp::Or,
+                            Expr::ident("done")
+                        )
+                    ),
+                    BinaryOp::Pipe,
+                    Expr::ident("f")
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr("nullable ?? null"),
+            Ok((
+                "",
+                Expr::binary_expr(
+                    Expr::ident("nullable"),
+                    BinaryOp::Coalesce,
+                    Expr::literal(Literal::Null)
+                )
+            ))
+        );
+        assert!(matches!(parse_expr("rec?."), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_parse_field_access() {
+        assert_eq!(
+            parse_expr(r#"row.size * rec."order id".total[0]"#),
+            Ok((
+                "",
+                Expr::binary_expr(
+                    Expr::field(Expr::ident("row"), "size"),
+                    BinaryOp::Mul,
+                    Expr::index(
+                        Expr::field(Expr::field(Expr::ident("rec"), "order id"), "total"),
+                        Expr::literal_number(0)
+                    )
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr(r#"rec["name"]"#),
+            Ok((
+                "",
+                Expr::index(Expr::ident("rec"), Expr::literal_string("name"))
+            ))
+        );
+        assert_eq!(
+            parse_expr("rec.`price.usd`?.`if`"),
+            Ok((
+                "",
+                Expr::safe_field(Expr::field(Expr::ident("rec"), "price.usd"), "if")
+            ))
+        );
+        assert!(matches!(parse_expr("rec."), Err(nom::Err::Failure(_))));
+
+        let (_, expr) = parse_expr("a.\"b c\"").unwrap();
+        let ExprKind::Field(_, name) = &expr.kind else {
+            panic!("expected a field access, got {:?}", expr);
+        };
+        assert_eq!((name.span.start, name.span.end), (2, 7));
+    }
+
+    #[test]
+    fn test_parse_spans() {
+        let (_, expr) = parse_expr("READ(\"in\") |>\n  HEAD(-10)").unwrap();
+        assert_eq!((expr.span.start, expr.span.end), (0, 25));
+        let ExprKind::BinaryExpr(left, _, right) = &expr.kind else {
+            panic!("expected a binary expression, got {:?}", expr);
+        };
+        assert_eq!(left.span, Span::new(0, 10, 1, 1));
+        assert_eq!(right.span, Span::new(16, 25, 2, 3));
+        assert_ne!(left.span, right.span);
+        // Equality of nodes leaves their spans out.
+        assert_eq!(
+            **left,
+            Expr::function_call("READ", vec![Expr::literal_string("in")])
+        );
+        let ExprKind::FunctionCall(name, args, _) = &right.kind else {
+            panic!("expected a function call, got {:?}", right);
+        };
+        assert_eq!((name.span.start, name.span.end), (16, 20));
+        assert_eq!((args[0].span.start, args[0].span.end), (21, 24));
+        assert_eq!(args[0].span.column, 8);
+
+        let (_, expr) = parse_expr("f(1,\n  pretty: true)").unwrap();
+        let ExprKind::FunctionCall(_, _, named) = &expr.kind else {
+            panic!("expected a function call, got {:?}", expr);
+        };
+        assert_eq!((named[0].0.span.line, named[0].0.span.column), (2, 3));
+        assert_eq!((named[0].1.span.start, named[0].1.span.end), (15, 19));
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        assert_eq!(
+            parse_expr("3.14 # a Number"),
+            Ok((
+                "",
+                commented(
+                    Expr::literal_number(
+                        BigDecimal::from_str("3.14").expect("unable to parse 3.14 into BigDecimal")
+                    ),
+                    &[],
+                    &[" a Number"]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr(
+                "# leading\nf( # open\n  1, # one\n  x: 2 # two\n) # call\n|> -\n# negate\ny"
+            ),
+            Ok((
+                "",
+                commented(
+                    Expr::binary_expr(
+                        commented(
+                            Expr::function_call_named(
+                                "f",
+                                vec![commented(Expr::literal_number(1), &[" open"], &[" one"])],
+                                vec![("x", commented(Expr::literal_number(2), &[], &[" two"]))]
+                            ),
+                            &[],
+                            &[" call"]
+                        ),
+                        BinaryOp::Pipe,
+                        Expr::unary_expr(
+                            UnaryOp::Minus,
+                            commented(Expr::ident("y"), &[" negate"], &[])
+                        )
+                    ),
+                    &[" leading"],
+                    &[]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_expr(r##""# not a comment" # a comment"##),
+            Ok((
+                "",
+                commented(
+                    Expr::literal_string("# not a comment"),
+                    &[],
+                    &[" a comment"]
+                )
+            ))
+        );
+        assert_ne!(
+            parse_expr("1 # one").unwrap().1,
+            parse_expr("1 # uno").unwrap().1
+        );
+    }
+
+    #[test]
+    fn test_parse_comment_trivia() {
+        let texts = |comments: &[crate::ast::Comment]| {
+            comments.iter().map(|c| c.text.clone()).collect::<Vec<_>>()
+        };
+
+        let (_, expr) = parse_expr("# first\n1 + # one\n# before two\n2 # two\n# last").unwrap();
+        assert_eq!(texts(&expr.trivia.leading), vec![" first"]);
+        assert_eq!(texts(&expr.trivia.trailing), vec![" two", " last"]);
+        let ExprKind::BinaryExpr(left, _, right) = &expr.kind else {
+            panic!("expected a binary expression, got {:?}", expr);
+        };
+        assert_eq!(texts(&left.trivia.trailing), vec![" one"]);
+        assert_eq!(texts(&right.trivia.leading), vec![" before two"]);
+        assert!(right.trivia.trailing.is_empty());
+
+        let comment = &left.trivia.trailing[0];
+        assert_eq!((comment.span.start, comment.span.end), (12, 17));
+        assert_eq!((comment.span.line, comment.span.column), (2, 5));
+    }
+
+    #[test]
+    fn test_located_error() {
+        let input = "\n  )";
+        let err = parse_expr(input).unwrap_err();
+        assert!(matches!(
+            crate::parser::located_error(input, err),
+            crate::Error::ParserError(message)
+                if message == "expected expression, found ')' at line 2, column 3\n  |\n2 |   )\n  |   ^"
+        ));
+    }
 }
@@ -1,61 +1,142 @@
-use nom::bytes::complete::tag;
-use nom::character::complete::multispace0;
-use nom::combinator::map;
+use nom::branch::alt;
+use nom::combinator::cut;
+use nom::combinator::recognize;
+use nom::combinator::verify;
+use nom::error::VerboseError;
+use nom::error::VerboseErrorKind;
 use nom::multi::separated_list0;
 use nom::sequence::delimited;
+use nom::sequence::terminated;
 use nom::sequence::tuple;
-use nom::IResult;
 
+use super::expr::ExprParser;
+use super::identifier::parse_name;
+use super::lexer::TokenKind;
+use super::IResult;
+use crate::ast::Expr;
+use crate::ast::ExprKind;
 use crate::ast::Identifier;
 
-use super::parse_identifier;
-
-/// Parses a function call: `Identifier` `(` Expr* `)`.
-/// Arguments are comma-separated. Returns `(name, args)`.
-pub fn parse_function_call<F, O>(
-    parse_expr: F,
-) -> impl Fn(&str) -> IResult<&str, (Identifier, Vec<O>)>
-where
-    F: Fn(&str) -> IResult<&str, O>,
-{
-    move |input: &str| {
-        let (input, name) = map(parse_identifier, |s: &str| Identifier(s.to_string()))(input)?;
-        let (input, _) = multispace0(input)?;
-        let (input, args) = delimited(
-            tag("("),
+/// A parsed argument list.
+#[derive(Debug, PartialEq)]
+pub struct CallArguments {
+    /// Positional arguments, in order.
+    pub args: Vec<Expr>,
+    /// Named arguments (`name: expr`), in order.
+    pub named: Vec<(Identifier, Expr)>,
+}
+
+/// An argument along with the input it starts at, for reporting misplaced arguments.
+enum Argument<'a> {
+    Positional(&'a str, Expr),
+    Named(&'a str, Identifier, Expr),
+}
+
+impl<'s> ExprParser<'s> {
+    /// Parses a function call: `Identifier` `(` Expr* `)`, where the names may be backtick-quoted.
+    /// The `(` must be on the same line as the name, so a name ending one line and a parenthesized
+    /// expression starting the next are not a call. See [`Self::parse_arguments`] for the
+    /// arguments.
+    pub(super) fn parse_function_call(&self, input: &'s str) -> IResult<&'s str, ExprKind> {
+        let (input, name) = self.parse_variable_name(input)?;
+        let (input, _) = verify(recognize(|i| self.ws(i)), |skipped: &str| {
+            !skipped.contains('\n')
+        })(input)?;
+        let (input, CallArguments { args, named }) = self.parse_arguments(input)?;
+        Ok((
+            input,
+            ExprKind::FunctionCall(self.identifier(name), args, named),
+        ))
+    }
+
+    /// Parses an argument list: `(` Expr* `)`.
+    /// Arguments are comma-separated: positional arguments first, then named arguments `name: expr`.
+    /// The `:` must immediately follow the name, so `f(a :b)` is never read as a named argument.
+    /// Once the opening `(` has been seen the input must be an argument list, so a malformed one,
+    /// a positional argument after a named one, or a repeated name is a failure.
+    pub(super) fn parse_arguments(&self, input: &'s str) -> IResult<&'s str, CallArguments> {
+        let (input, _) = self.punct("(")(input)?;
+        let argument = |input: &'s str| {
+            alt((
+                |input| {
+                    let (rest, name) =
+                        terminated(self.lexeme(NAMES, parse_name), self.punct(":"))(input)?;
+                    let (rest, _) = self.ws(rest)?;
+                    let (rest, value) = cut(|i| self.parse_inner(i))(rest)?;
+                    Ok((rest, Argument::Named(input, self.identifier(name), value)))
+                },
+                |input| {
+                    let (rest, value) = self.parse_inner(input)?;
+                    Ok((rest, Argument::Positional(input, value)))
+                },
+            ))(input)
+        };
+        let (input, arguments) = cut(terminated(
             delimited(
-                multispace0,
-                separated_list0(tuple((multispace0, tag(","), multispace0)), &parse_expr),
-                multispace0,
+                |i| self.ws(i),
+                separated_list0(
+                    tuple((|i| self.ws(i), self.char(','), |i| self.ws(i))),
+                    argument,
+                ),
+                |i| self.ws(i),
             ),
-            tag(")"),
-        )(input)?;
-        Ok((input, (name, args)))
+            self.char(')'),
+        ))(input)?;
+
+        let mut args = vec![];
+        let mut named: Vec<(Identifier, Expr)> = vec![];
+        for argument in arguments {
+            match argument {
+                Argument::Positional(at, _) if !named.is_empty() => {
+                    return Err(failure(at, "named argument"));
+                }
+                Argument::Positional(_, value) => args.push(value),
+                Argument::Named(at, name, _) if named.iter().any(|(n, _)| *n == name) => {
+                    return Err(failure(at, "unique argument name"));
+                }
+                Argument::Named(_, name, value) => named.push((name, value)),
+            }
+        }
+        Ok((input, CallArguments { args, named }))
     }
 }
 
+/// The classes of token that can name an argument: any name, reserved words included.
+const NAMES: &[TokenKind] = &[
+    TokenKind::Identifier,
+    TokenKind::Keyword,
+    TokenKind::Boolean,
+    TokenKind::Null,
+    TokenKind::Operator,
+];
+
+fn failure<'a>(at: &'a str, expected: &'static str) -> nom::Err<VerboseError<&'a str>> {
+    nom::Err::Failure(VerboseError {
+        errors: vec![(at, VerboseErrorKind::Context(expected))],
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr as _;
 
     use bigdecimal::BigDecimal;
 
-    use super::parse_function_call;
+    use super::*;
     use crate::ast::Expr;
-    use crate::parser::expr::parse_expr;
 
-    use crate::ast::Identifier;
+    fn parse_function_call(input: &str) -> IResult<&str, Expr> {
+        let (rest, kind) = ExprParser::new(input).parse_function_call(input)?;
+        Ok((rest, kind.into()))
+    }
 
     #[test]
     fn test_parse_trim() {
         assert_eq!(
-            parse_function_call(parse_expr)(r#"trim("string")"#),
+            parse_function_call(r#"trim("string")"#),
             Ok((
                 "",
-                (
-                    Identifier::try_from("trim").expect("invalid identifier"),
-                    vec![Expr::literal_string("string")]
-                )
+                Expr::function_call("trim", vec![Expr::literal_string("string")])
             ))
         );
     }
@@ -63,11 +144,11 @@ mod tests {
     #[test]
     fn test_parse_floor() {
         assert_eq!(
-            parse_function_call(parse_expr)("floor(3.14)"),
+            parse_function_call("floor(3.14)"),
             Ok((
                 "",
-                (
-                    Identifier::try_from("floor").expect("invalid identifier"),
+                Expr::function_call(
+                    "floor",
                     vec![Expr::literal_number(
                         BigDecimal::from_str("3.14").expect("unable to parse 3.14 into BigDecimal")
                     )]
@@ -79,11 +160,11 @@ mod tests {
     #[test]
     fn test_parse_ceil() {
         assert_eq!(
-            parse_function_call(parse_expr)("ceil(3.14)"),
+            parse_function_call("ceil(3.14)"),
             Ok((
                 "",
-                (
-                    Identifier::try_from("ceil").expect("invalid identifier"),
+                Expr::function_call(
+                    "ceil",
                     vec![Expr::literal_number(
                         BigDecimal::from_str("3.14").expect("unable to parse 3.14 into BigDecimal")
                     )]
@@ -95,11 +176,11 @@ mod tests {
     #[test]
     fn test_parse_round() {
         assert_eq!(
-            parse_function_call(parse_expr)("round(3.14, 2)"),
+            parse_function_call("round(3.14, 2)"),
             Ok((
                 "",
-                (
-                    Identifier::try_from("round").expect("invalid identifier"),
+                Expr::function_call(
+                    "round",
                     vec![
                         Expr::literal_number(
                             BigDecimal::from_str("3.14")
@@ -111,4 +192,72 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn test_parse_named_arguments() {
+        assert_eq!(
+            parse_function_call(r#"WRITE("test.json", pretty: true)"#),
+            Ok((
+                "",
+                Expr::function_call_named(
+                    "WRITE",
+                    vec![Expr::literal_string("test.json")],
+                    vec![("pretty", Expr::literal_boolean(true))]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_function_call("f(mode: :fast)"),
+            Ok((
+                "",
+                Expr::function_call_named(
+                    "f",
+                    vec![],
+                    vec![("mode", Expr::literal_symbol("fast"))]
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_symbol_argument_is_not_named() {
+        assert_eq!(
+            parse_function_call("f(a, :b)"),
+            Ok((
+                "",
+                Expr::function_call("f", vec![Expr::ident("a"), Expr::literal_symbol("b")])
+            ))
+        );
+        assert!(matches!(
+            parse_function_call("f(a :b)"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_duplicate_named_argument() {
+        let input = "f(a: 1, a: 2)";
+        let Err(nom::Err::Failure(e)) = parse_function_call(input) else {
+            panic!("duplicate names should fail");
+        };
+        assert_eq!(
+            e.errors,
+            vec![(
+                &input[8..],
+                VerboseErrorKind::Context("unique argument name")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_parse_positional_after_named_argument() {
+        let input = "f(a: 1, 2)";
+        let Err(nom::Err::Failure(e)) = parse_function_call(input) else {
+            panic!("a positional argument after a named one should fail");
+        };
+        assert_eq!(
+            e.errors,
+            vec![(&input[8..], VerboseErrorKind::Context("named argument"))]
+        );
+    }
 }
@@ -1,13 +1,148 @@
-use nom::bytes::complete::take_while1;
-use nom::IResult;
+use std::borrow::Cow;
 
-/// Parses an identifier: one or more alphanumeric, hyphen, or underscore characters.
+use nom::branch::alt;
+use nom::bytes::complete::is_not;
+use nom::bytes::complete::tag;
+use nom::bytes::complete::take_while;
+use nom::character::complete::anychar;
+use nom::character::complete::char;
+use nom::character::complete::satisfy;
+use nom::combinator::cut;
+use nom::combinator::map_res;
+use nom::combinator::peek;
+use nom::combinator::recognize;
+use nom::combinator::verify;
+use nom::error::context;
+use nom::error::ErrorKind;
+use nom::error::VerboseError;
+use nom::error::VerboseErrorKind;
+use nom::multi::many0;
+use nom::sequence::pair;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
+use nom::sequence::tuple;
+use unicode_ident::is_xid_continue;
+use unicode_ident::is_xid_start;
+
+use super::lexer::KEYWORDS;
+use super::IResult;
+
+/// Parses a bare identifier: words that start with an `XID_Start` character or `_` and continue
+/// with `XID_Continue` characters (letters, digits and `_` in any script), joined by single
+/// hyphens (kebab-case, as in `order-id`). This is the rule [`Identifier::try_from`] checks.
+///
+/// A hyphen is part of an identifier only between two words, and a word cannot start with a
+/// digit, so `x-1` is `x - 1` and `a-` is `a` followed by `-`; `a-b` is one identifier, so
+/// subtracting `b` from `a` needs spaces: `a - b`.
+///
+/// [`Identifier::try_from`]: crate::ast::Identifier
 pub fn parse_identifier(input: &str) -> IResult<&str, &str> {
-    take_while1(|c: char| c.is_alphanumeric() || c == '-' || c == '_')(input)
+    let word = || {
+        recognize(pair(
+            satisfy(|c| c == '_' || is_xid_start(c)),
+            take_while(is_xid_continue),
+        ))
+    };
+    recognize(tuple((
+        word(),
+        many0(tuple((
+            char('-'),
+            peek(satisfy(|c| c == '_' || is_xid_start(c))),
+            word(),
+        ))),
+    )))(input)
+}
+
+/// Parses one of `words` as a whole bare identifier, so that it does not match the start of a
+/// longer name: `parse_word(&["null"])` rejects `nullable` and `null-ish`.
+pub(super) fn parse_word(words: &'static [&'static str]) -> impl Fn(&str) -> IResult<&str, &str> {
+    move |input| verify(parse_identifier, |word: &str| words.contains(&word))(input)
+}
+
+/// Parses a backtick-quoted identifier, such as `` `order id` `` or `` `price.usd` ``, returning
+/// its name. Any text but an empty name is allowed; `` \` `` and `\\` escape a backtick and a
+/// backslash. Once the opening backtick has been seen, anything malformed is a failure.
+pub fn parse_quoted_identifier(input: &str) -> IResult<&str, String> {
+    preceded(
+        tag("`"),
+        cut(terminated(
+            context(
+                "quoted identifier",
+                map_res(
+                    recognize(many0(alt((
+                        is_not("`\\"),
+                        recognize(pair(char('\\'), anychar)),
+                    )))),
+                    unescape,
+                ),
+            ),
+            char('`'),
+        )),
+    )(input)
+}
+
+/// Parses a name in either form, bare or backtick-quoted, returning its source text.
+/// Use [`identifier_name`] to get the name it stands for.
+pub fn parse_name(input: &str) -> IResult<&str, &str> {
+    alt((parse_identifier, recognize(parse_quoted_identifier)))(input)
+}
+
+/// Parses a name being bound: by `let` or `def`, as a parameter, or in a pattern. A bare name
+/// cannot be a reserved word, so `let true = 1` is a syntax error, but a quoted one can:
+/// `` let `true` = 1 ``.
+pub fn parse_binding_name(input: &str) -> IResult<&str, &str> {
+    let (rest, name) = parse_name(input)?;
+    if is_reserved(name) {
+        return Err(nom::Err::Failure(VerboseError {
+            errors: vec![
+                (input, VerboseErrorKind::Nom(ErrorKind::MapRes)),
+                (
+                    input,
+                    VerboseErrorKind::Context("name that is not a reserved word"),
+                ),
+            ],
+        }));
+    }
+    Ok((rest, name))
+}
+
+/// Whether `word` is reserved, as one of the [`KEYWORDS`].
+pub fn is_reserved(word: &str) -> bool {
+    KEYWORDS.contains(&word)
+}
+
+/// The name that `text`, as recognized by [`parse_name`], stands for.
+pub fn identifier_name(text: &str) -> Cow<'_, str> {
+    match parse_quoted_identifier(text) {
+        Ok((_, name)) => Cow::Owned(name),
+        Err(_) => Cow::Borrowed(text),
+    }
+}
+
+fn unescape(quoted: &str) -> Result<String, String> {
+    if quoted.is_empty() {
+        return Err("empty identifier".to_string());
+    }
+    let mut name = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('`' | '\\')) => name.push(c),
+                Some(c) => return Err(format!("invalid escape sequence '\\{}'", c)),
+                None => return Err("unterminated escape sequence".to_string()),
+            },
+            c => name.push(c),
+        }
+    }
+    Ok(name)
 }
 
 #[cfg(test)]
 mod tests {
+    use nom::error::ErrorKind;
+    use nom::error::VerboseErrorKind;
+
     use super::*;
 
     #[test]
@@ -15,8 +150,68 @@ mod tests {
         assert_eq!(parse_identifier("foo"), Ok(("", "foo")));
         assert_eq!(parse_identifier("abc123"), Ok(("", "abc123")));
         assert_eq!(parse_identifier("abc_123"), Ok(("", "abc_123")));
-        assert_eq!(parse_identifier("abc-123"), Ok(("", "abc-123")));
+        assert_eq!(parse_identifier("abc-def"), Ok(("", "abc-def")));
+        assert_eq!(parse_identifier("order-id_2-b"), Ok(("", "order-id_2-b")));
         assert_eq!(parse_identifier("xyz"), Ok(("", "xyz")));
         assert_eq!(parse_identifier("foo bar"), Ok((" bar", "foo")));
     }
+
+    #[test]
+    fn test_parse_identifier_hyphens() {
+        assert_eq!(parse_identifier("abc-123"), Ok(("-123", "abc")));
+        assert_eq!(parse_identifier("x-1"), Ok(("-1", "x")));
+        assert_eq!(parse_identifier("a-"), Ok(("-", "a")));
+        assert_eq!(parse_identifier("a--b"), Ok(("--b", "a")));
+        assert!(parse_identifier("-a").is_err());
+    }
+
+    #[test]
+    fn test_parse_unicode_identifier() {
+        assert_eq!(parse_identifier("prix_été + 1"), Ok((" + 1", "prix_été")));
+        assert_eq!(parse_identifier("数量"), Ok(("", "数量")));
+        assert_eq!(parse_identifier("_tmp"), Ok(("", "_tmp")));
+        assert!(parse_identifier("2nd").is_err());
+        assert!(parse_identifier("·a").is_err());
+    }
+
+    #[test]
+    fn test_parse_quoted_identifier() {
+        assert_eq!(
+            parse_quoted_identifier("`order id` + 1"),
+            Ok((" + 1", "order id".to_string()))
+        );
+        assert_eq!(
+            parse_quoted_identifier(r"`a\`b\\c`"),
+            Ok(("", r"a`b\c".to_string()))
+        );
+        assert_eq!(parse_name("`price.usd`.x"), Ok((".x", "`price.usd`")));
+        assert_eq!(identifier_name("`price.usd`"), "price.usd");
+        assert_eq!(identifier_name("price"), "price");
+        for input in ["``", r"`a\nb`"] {
+            let Err(nom::Err::Failure(e)) = parse_quoted_identifier(input) else {
+                panic!("{} should fail", input);
+            };
+            assert_eq!(
+                e.errors[0],
+                (&input[1..], VerboseErrorKind::Nom(ErrorKind::MapRes))
+            );
+        }
+        assert!(matches!(
+            parse_quoted_identifier("`open"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_binding_name() {
+        assert_eq!(parse_binding_name("total = 1"), Ok((" = 1", "total")));
+        assert_eq!(parse_binding_name("`true`"), Ok(("", "`true`")));
+        assert_eq!(parse_binding_name("iffy"), Ok(("", "iffy")));
+        for input in ["true", "false", "null", "if", "in", "let", "fn", "match"] {
+            assert!(
+                matches!(parse_binding_name(input), Err(nom::Err::Failure(_))),
+                "{input}"
+            );
+        }
+    }
 }
@@ -0,0 +1,126 @@
+use nom::combinator::cut;
+use nom::error::context;
+use nom::error::VerboseError;
+use nom::error::VerboseErrorKind;
+use nom::multi::separated_list0;
+use nom::sequence::delimited;
+use nom::sequence::terminated;
+use nom::sequence::tuple;
+
+use super::expr::ExprParser;
+use super::IResult;
+use crate::ast::ExprKind;
+use crate::ast::Identifier;
+
+impl<'s> ExprParser<'s> {
+    /// Parses a lambda: `fn` `(` Identifier* `)` `=>` Expr.
+    /// `fn` followed by `(` always starts a lambda, so anything malformed after that is a failure.
+    pub(super) fn parse_lambda(&self, input: &'s str) -> IResult<&'s str, ExprKind> {
+        let (input, _) = tuple((self.keyword("fn"), |i| self.ws(i), self.punct("(")))(input)?;
+        let (input, (params, _, _, _, body)) = cut(tuple((
+            |i| self.parse_parameters(i),
+            |i| self.ws(i),
+            context("'=>'", self.punct("=>")),
+            |i| self.ws(i),
+            |i| self.parse_pipe(i),
+        )))(input)?;
+        Ok((input, ExprKind::Lambda(params, Box::new(body))))
+    }
+
+    /// Parses a parameter list following its opening `(`: comma-separated names, bare or quoted,
+    /// then `)`.
+    /// A repeated name is a failure.
+    pub(super) fn parse_parameters(&self, input: &'s str) -> IResult<&'s str, Vec<Identifier>> {
+        let param = |input: &'s str| {
+            let (rest, name) = context("parameter", |i| self.parse_binding_name(i))(input)?;
+            Ok((rest, (input, self.identifier(name))))
+        };
+        let (input, params) = terminated(
+            delimited(
+                |i| self.ws(i),
+                separated_list0(
+                    tuple((|i| self.ws(i), self.char(','), |i| self.ws(i))),
+                    param,
+                ),
+                |i| self.ws(i),
+            ),
+            self.char(')'),
+        )(input)?;
+
+        let mut names: Vec<Identifier> = vec![];
+        for (at, name) in params {
+            if names.contains(&name) {
+                return Err(nom::Err::Failure(VerboseError {
+                    errors: vec![(at, VerboseErrorKind::Context("unique parameter name"))],
+                }));
+            }
+            names.push(name);
+        }
+        Ok((input, names))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::BinaryOp;
+    use crate::ast::Expr;
+
+    fn parse_lambda(input: &str) -> IResult<&str, Expr> {
+        let (rest, kind) = ExprParser::new(input).parse_lambda(input)?;
+        Ok((rest, kind.into()))
+    }
+
+    #[test]
+    fn test_parse_lambda() {
+        assert_eq!(
+            parse_lambda("fn(x, y) => x + y"),
+            Ok((
+                "",
+                Expr::lambda(
+                    vec!["x", "y"],
+                    Expr::binary_expr(Expr::ident("x"), BinaryOp::Add, Expr::ident("y"))
+                )
+            ))
+        );
+        assert_eq!(
+            parse_lambda("fn () => 1"),
+            Ok(("", Expr::lambda(vec![], Expr::literal_number(1))))
+        );
+    }
+
+    #[test]
+    fn test_parse_fn_prefix_is_not_a_lambda() {
+        assert!(matches!(
+            parse_lambda("fnord(x) => x"),
+            Err(nom::Err::Error(_))
+        ));
+        assert!(matches!(parse_lambda("fn"), Err(nom::Err::Error(_))));
+    }
+
+    #[test]
+    fn test_parse_malformed_lambda() {
+        let input = "fn(x) x";
+        let Err(nom::Err::Failure(e)) = parse_lambda(input) else {
+            panic!("a missing `=>` should fail");
+        };
+        assert!(e
+            .errors
+            .contains(&(&input[6..], VerboseErrorKind::Context("'=>'"))));
+    }
+
+    #[test]
+    fn test_parse_duplicate_parameter() {
+        let input = "fn(x, x) => x";
+        let Err(nom::Err::Failure(e)) = parse_lambda(input) else {
+            panic!("duplicate parameters should fail");
+        };
+        assert_eq!(
+            e.errors,
+            vec![(
+                &input[6..],
+                VerboseErrorKind::Context("unique parameter name")
+            )]
+        );
+    }
+}
@@ -0,0 +1,554 @@
+use std::cell::RefCell;
+
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::multispace0;
+use nom::character::complete::one_of;
+use nom::character::complete::satisfy;
+use nom::combinator::fail;
+use nom::combinator::map;
+use nom::combinator::peek;
+use nom::combinator::value;
+use nom::sequence::preceded;
+
+use super::identifier::parse_identifier;
+use super::identifier::parse_quoted_identifier;
+use super::number::parse_number;
+use super::operands::parse_binary_op;
+use super::operands::parse_unary_op;
+use super::regex::parse_regex;
+use super::source::Source;
+use super::string::parse_interpolated_string;
+use super::string::parse_string;
+use super::symbol::parse_symbol;
+use super::time::parse_date_time;
+use super::time::parse_duration;
+use super::trivia::parse_comment;
+use super::IResult;
+use crate::ast::Span;
+use crate::Error;
+
+/// Words that are identifiers in form but reserved by the grammar, so that none can be a bare
+/// name. `true`, `false` and `null` are tokenized as literals and `in` as an operator; the others
+/// are [`TokenKind::Keyword`]s. `step` is not one: it is a keyword only after a range
+/// (`0..10 step 2`), and a name anywhere else.
+pub const KEYWORDS: &[&str] = &[
+    "def", "else", "false", "fn", "if", "in", "let", "match", "null", "then", "true",
+];
+
+/// The class of a token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    Number,
+    /// A string in any form. An interpolated string is a single token, holes included.
+    String,
+    Symbol,
+    Boolean,
+    Null,
+    /// A date or timestamp: `@2024-01-31`, `@2024-01-31T12:00:00Z`.
+    DateTime,
+    Duration,
+    /// A regex literal: `~r"^[a-z]+$"i`.
+    Regex,
+    /// A name, bare or backtick-quoted.
+    Identifier,
+    Keyword,
+    Operator,
+    /// Brackets, separators and other punctuation: `( ) [ ] { } , ; : . = => .. ?.`.
+    Punctuation,
+    Comment,
+}
+
+/// A token: its class, its text, and where it appears in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// Splits `input` into tokens, skipping whitespace and keeping comments.
+///
+/// Input that cannot start a token, and unterminated strings, are reported as a located
+/// [`Error::LexerError`]; a malformed token, such as an unknown escape sequence or a bad
+/// hexadecimal number, is an [`Error::SyntaxError`] as when parsing.
+pub fn tokenize(input: &str) -> Result<Vec<Token<'_>>, Error> {
+    let (mut tokens, lexed) = lex(input);
+    lexed?;
+    // Leave out the tokens in the holes of interpolated strings, which lie within them.
+    let mut end = 0;
+    tokens.retain(|token| {
+        let outer = token.span.start >= end;
+        if outer {
+            end = token.span.end;
+        }
+        outer
+    });
+    Ok(tokens)
+}
+
+/// Splits `input` into tokens as [`tokenize`] does, along with the tokens in the holes of
+/// interpolated strings, all in source order. This is the stream the parser reads. Lexing stops
+/// at the first error, which is returned with the tokens before it.
+pub(super) fn lex(input: &str) -> (Vec<Token<'_>>, Result<(), Error>) {
+    let source = Source::new(input);
+    let holes = RefCell::new(vec![]);
+    let mut tokens: Vec<Token> = vec![];
+    let mut lexed = Ok(());
+    let mut rest = input.trim_start_matches(WHITESPACE);
+    // How many brackets are open, and whether a newline precedes the next token. Outside
+    // brackets a newline ends a statement, so a `:` at the start of a line starts a symbol.
+    let mut depth = 0usize;
+    let (mut new_line, mut adjacent) = (false, false);
+    while !rest.is_empty() {
+        let mut significant = tokens.iter().rev().filter(|t| t.kind != TokenKind::Comment);
+        let last = significant.next().map(|t| (t.kind, t.text));
+        let before_last = significant.next().map(|t| (t.kind, t.text));
+        let symbol = depth == 0 && new_line || starts_symbol(last, before_last, adjacent);
+        let (remaining, kind) = match lex_token(rest, symbol, &holes) {
+            Ok(lexed) => lexed,
+            Err(e) => {
+                lexed = Err(lex_error(&source, rest, e));
+                break;
+            }
+        };
+        let text = &rest[..rest.len() - remaining.len()];
+        match text {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        tokens.push(Token {
+            kind,
+            text,
+            span: source.span(rest, remaining),
+        });
+        rest = remaining.trim_start_matches(WHITESPACE);
+        let skipped = &remaining[..remaining.len() - rest.len()];
+        (new_line, adjacent) = (skipped.contains('\n'), skipped.is_empty());
+    }
+    tokens.extend(holes.into_inner().into_iter().map(|(kind, text)| Token {
+        kind,
+        text,
+        span: source.span_of(text),
+    }));
+    tokens.sort_by_key(|token| token.span.start);
+    (tokens, lexed)
+}
+
+/// The characters skipped between tokens.
+pub(super) const WHITESPACE: [char; 4] = [' ', '\t', '\r', '\n'];
+
+/// The tokens lexed in the holes of interpolated strings so far.
+type Holes<'a> = RefCell<Vec<(TokenKind, &'a str)>>;
+
+fn lex_token<'a>(input: &'a str, symbol: bool, holes: &Holes<'a>) -> IResult<&'a str, TokenKind> {
+    alt((
+        value(TokenKind::Comment, parse_comment),
+        value(TokenKind::String, |i| lex_string(i, holes)),
+        value(TokenKind::DateTime, parse_date_time),
+        value(TokenKind::Regex, parse_regex),
+        value(TokenKind::Duration, parse_duration),
+        value(
+            TokenKind::Number,
+            preceded(peek(satisfy(|c| c.is_ascii_digit())), parse_number),
+        ),
+        |i| {
+            if symbol {
+                value(TokenKind::Symbol, parse_symbol)(i)
+            } else {
+                fail(i)
+            }
+        },
+        value(
+            TokenKind::Punctuation,
+            alt((tag("=>"), tag("..="), tag(".."), tag("?."))),
+        ),
+        value(TokenKind::Operator, parse_binary_op),
+        value(TokenKind::Operator, parse_unary_op),
+        value(TokenKind::Identifier, parse_quoted_identifier),
+        map(parse_identifier, |word| match word {
+            "true" | "false" => TokenKind::Boolean,
+            "null" => TokenKind::Null,
+            _ if KEYWORDS.contains(&word) => TokenKind::Keyword,
+            _ => TokenKind::Identifier,
+        }),
+        value(TokenKind::Punctuation, one_of("()[]{},;:.=")),
+    ))(input)
+}
+
+/// Lexes a string in any form, recording the tokens in its holes if it is interpolated.
+fn lex_string<'a>(input: &'a str, holes: &Holes<'a>) -> IResult<&'a str, ()> {
+    let recorded = holes.borrow().len();
+    match parse_interpolated_string(|i| lex_hole(i, holes), multispace0)(input) {
+        Ok((rest, _)) => Ok((rest, ())),
+        Err(nom::Err::Error(_)) => {
+            holes.borrow_mut().truncate(recorded);
+            value((), parse_string)(input)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Lexes the tokens of an interpolation hole, up to the `}` that closes it.
+fn lex_hole<'a>(input: &'a str, holes: &Holes<'a>) -> IResult<&'a str, ()> {
+    let mut depth = 0;
+    let mut rest = input.trim_start_matches(WHITESPACE);
+    let (mut last, mut before_last): (Option<(TokenKind, &str)>, _) = (None, None);
+    let mut adjacent = false;
+    while !(rest.is_empty() || depth == 0 && rest.starts_with('}')) {
+        let symbol = starts_symbol(last, before_last, adjacent);
+        let (remaining, kind) = lex_token(rest, symbol, holes)?;
+        let text = &rest[..rest.len() - remaining.len()];
+        match text {
+            "{" => depth += 1,
+            "}" => depth -= 1,
+            _ => {}
+        }
+        holes.borrow_mut().push((kind, text));
+        if kind != TokenKind::Comment {
+            (last, before_last) = (Some((kind, text)), last);
+        }
+        rest = remaining.trim_start_matches(WHITESPACE);
+        adjacent = rest.len() == remaining.len();
+    }
+    Ok((rest, ()))
+}
+
+/// Whether a `:` after the tokens `last` and `before_last`, directly after `last` if `adjacent`,
+/// starts a symbol. It does not when it follows a value, where it separates a record key from
+/// its value or the bounds of a slice, nor at the start of a subscript, where it begins a slice
+/// (`xs[:2]`), nor directly after a keyword that starts a record field or argument, as in
+/// `{if: 1}` or `f(match: x)`.
+fn starts_symbol(
+    last: Option<(TokenKind, &str)>,
+    before_last: Option<(TokenKind, &str)>,
+    adjacent: bool,
+) -> bool {
+    let ends_value = |token: Option<(TokenKind, &str)>| match token {
+        Some((TokenKind::Punctuation, text)) => matches!(text, ")" | "]" | "}"),
+        Some((kind, _)) => matches!(
+            kind,
+            TokenKind::Number
+                | TokenKind::String
+                | TokenKind::Symbol
+                | TokenKind::Boolean
+                | TokenKind::Null
+                | TokenKind::DateTime
+                | TokenKind::Duration
+                | TokenKind::Regex
+                | TokenKind::Identifier
+        ),
+        None => false,
+    };
+    let opens_subscript = last == Some((TokenKind::Punctuation, "[")) && ends_value(before_last);
+    let names_field = adjacent
+        && matches!(last, Some((TokenKind::Keyword | TokenKind::Operator, text)) if KEYWORDS.contains(&text))
+        && matches!(before_last, Some((TokenKind::Punctuation, "{" | "(" | ",")));
+    !ends_value(last) && !opens_subscript && !names_field
+}
+
+/// Converts a failure to lex a token at `at` into a located error.
+fn lex_error(source: &Source, at: &str, err: nom::Err<nom::error::VerboseError<&str>>) -> Error {
+    match err {
+        nom::Err::Failure(_) => match source.error(err) {
+            Error::ParserError(message) => Error::LexerError(message),
+            error => error,
+        },
+        _ => {
+            let message = match at.chars().next() {
+                Some('"') => "unterminated string".to_string(),
+                Some(c) => format!("unexpected character {:?}", c),
+                None => "unexpected end of input".to_string(),
+            };
+            Error::LexerError(source.located(message, at))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.kind, t.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("let tier = if amount >= 1_000 then :gold else :std # note\n!done"),
+            vec![
+                (Keyword, "let"),
+                (Identifier, "tier"),
+                (Punctuation, "="),
+                (Keyword, "if"),
+                (Identifier, "amount"),
+                (Operator, ">="),
+                (Number, "1_000"),
+                (Keyword, "then"),
+                (Symbol, ":gold"),
+                (Keyword, "else"),
+                (Symbol, ":std"),
+                (Comment, "# note"),
+                (Operator, "!"),
+                (Identifier, "done"),
+            ]
+        );
+        assert_eq!(
+            kinds(r#"f(x, -1, r"a\b") |> g"#),
+            vec![
+                (Identifier, "f"),
+                (Punctuation, "("),
+                (Identifier, "x"),
+                (Punctuation, ","),
+                (Operator, "-"),
+                (Number, "1"),
+                (Punctuation, ","),
+                (String, r#"r"a\b""#),
+                (Punctuation, ")"),
+                (Operator, "|>"),
+                (Identifier, "g"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_colons() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("{a: :b}"),
+            vec![
+                (Punctuation, "{"),
+                (Identifier, "a"),
+                (Punctuation, ":"),
+                (Symbol, ":b"),
+                (Punctuation, "}"),
+            ]
+        );
+        assert_eq!(
+            kinds("xs[:n]"),
+            vec![
+                (Identifier, "xs"),
+                (Punctuation, "["),
+                (Punctuation, ":"),
+                (Identifier, "n"),
+                (Punctuation, "]"),
+            ]
+        );
+        assert_eq!(
+            kinds("[:a]"),
+            vec![(Punctuation, "["), (Symbol, ":a"), (Punctuation, "]")]
+        );
+        assert_eq!(
+            kinds("let x = y\n:ok"),
+            vec![
+                (Keyword, "let"),
+                (Identifier, "x"),
+                (Punctuation, "="),
+                (Identifier, "y"),
+                (Symbol, ":ok"),
+            ]
+        );
+        assert_eq!(
+            kinds("{a\n: b}"),
+            vec![
+                (Punctuation, "{"),
+                (Identifier, "a"),
+                (Punctuation, ":"),
+                (Identifier, "b"),
+                (Punctuation, "}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_interpolated_string() {
+        assert_eq!(
+            kinds(r#""a ${ {b: "}"}.b } c" + 1"#),
+            vec![
+                (TokenKind::String, r#""a ${ {b: "}"}.b } c""#),
+                (TokenKind::Operator, "+"),
+                (TokenKind::Number, "1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_reserved_word_keys() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("{if:x}"),
+            vec![
+                (Punctuation, "{"),
+                (Keyword, "if"),
+                (Punctuation, ":"),
+                (Identifier, "x"),
+                (Punctuation, "}"),
+            ]
+        );
+        assert_eq!(
+            kinds("(if :a"),
+            vec![(Punctuation, "("), (Keyword, "if"), (Symbol, ":a")]
+        );
+    }
+
+    #[test]
+    fn test_lex_holes() {
+        use TokenKind::*;
+        let input = "\"a ${b # c\n}\" + 1";
+        let (tokens, lexed) = lex(input);
+        assert!(lexed.is_ok());
+        assert_eq!(
+            tokens.iter().map(|t| (t.kind, t.text)).collect::<Vec<_>>(),
+            vec![
+                (String, "\"a ${b # c\n}\""),
+                (Identifier, "b"),
+                (Comment, "# c"),
+                (Operator, "+"),
+                (Number, "1"),
+            ]
+        );
+        let (tokens, lexed) = lex("x + \"abc");
+        assert!(matches!(lexed, Err(Error::LexerError(_))));
+        assert_eq!(tokens.len(), 2);
+    }
+
+    #[test]
+    fn test_tokenize_identifiers() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("`order id` + größe-2 - `if`"),
+            vec![
+                (Identifier, "`order id`"),
+                (Operator, "+"),
+                (Identifier, "größe"),
+                (Operator, "-"),
+                (Number, "2"),
+                (Operator, "-"),
+                (Identifier, "`if`"),
+            ]
+        );
+        assert!(matches!(
+            tokenize("x + `open"),
+            Err(Error::LexerError(message)) if message.starts_with("expected '`'")
+        ));
+    }
+
+    #[test]
+    fn test_tokenize_null() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("row?.price ?? null"),
+            vec![
+                (Identifier, "row"),
+                (Punctuation, "?."),
+                (Identifier, "price"),
+                (Operator, "??"),
+                (Null, "null"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_time_literals() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("at >= @2024-01-31T00:00:00Z - 1h30m"),
+            vec![
+                (Identifier, "at"),
+                (Operator, ">="),
+                (DateTime, "@2024-01-31T00:00:00Z"),
+                (Operator, "-"),
+                (Duration, "1h30m"),
+            ]
+        );
+        assert!(matches!(
+            tokenize("@2024-02-30"),
+            Err(Error::SyntaxError(message)) if message.starts_with("expected date")
+        ));
+    }
+
+    #[test]
+    fn test_tokenize_regex() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds(r#"line !~ ~r"^\s*#"m"#),
+            vec![
+                (Identifier, "line"),
+                (Operator, "!~"),
+                (Regex, r#"~r"^\s*#"m"#),
+            ]
+        );
+        assert!(matches!(
+            tokenize("name =~ ~r\"[a-\""),
+            Err(Error::SyntaxError(message))
+                if message.starts_with("expected regular expression, found 'r' at line 1, column 10")
+        ));
+    }
+
+    #[test]
+    fn test_tokenize_ranges() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("i in 1..=n step 2 && xs[0..5] != index"),
+            vec![
+                (Identifier, "i"),
+                (Operator, "in"),
+                (Number, "1"),
+                (Punctuation, "..="),
+                (Identifier, "n"),
+                (Identifier, "step"),
+                (Number, "2"),
+                (Operator, "&&"),
+                (Identifier, "xs"),
+                (Punctuation, "["),
+                (Number, "0"),
+                (Punctuation, ".."),
+                (Number, "5"),
+                (Punctuation, "]"),
+                (Operator, "!="),
+                (Identifier, "index"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let tokens = tokenize("1 +\n  foo").unwrap();
+        let foo = &tokens[2];
+        assert_eq!((foo.span.start, foo.span.end), (6, 9));
+        assert_eq!((foo.span.line, foo.span.column), (2, 3));
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert!(matches!(
+            tokenize("x + \"abc"),
+            Err(Error::LexerError(message))
+                if message.starts_with("unterminated string at line 1, column 5")
+        ));
+        assert!(matches!(
+            tokenize("1 $ 2"),
+            Err(Error::LexerError(message))
+                if message.starts_with("unexpected character '$' at line 1, column 3")
+        ));
+        assert!(matches!(
+            tokenize("r#\"abc\""),
+            Err(Error::LexerError(message)) if message.starts_with("expected end of raw string")
+        ));
+        assert!(matches!(
+            tokenize("\"${x"),
+            Err(Error::LexerError(message)) if message.starts_with("expected '}'")
+        ));
+        assert!(matches!(
+            tokenize("\"\\q\""),
+            Err(Error::SyntaxError(message)) if message.starts_with("expected escape sequence")
+        ));
+    }
+}
@@ -1,19 +1,29 @@
 use nom::branch::alt;
 use nom::combinator::map;
-use nom::IResult;
+use nom::combinator::value;
 
 use super::boolean::parse_boolean;
+use super::identifier::parse_word;
 use super::number::parse_number;
+use super::regex::parse_regex;
 use super::string::parse_string;
 use super::symbol::parse_symbol;
+use super::time::parse_date_time;
+use super::time::parse_duration;
+use super::IResult;
 use crate::ast::Literal;
 
-/// Parses a literal: number, string, boolean, or symbol.
+/// Parses a literal: number, string, boolean, symbol, date, timestamp, duration, regex, or `null`.
+/// `null`, `true` and `false` must be whole words, so `nullable` is a name.
 pub fn parse_literal(input: &str) -> IResult<&str, Literal> {
     alt((
+        value(Literal::Null, parse_word(&["null"])),
         map(parse_boolean, Literal::Boolean),
         map(parse_symbol, Literal::symbol),
         map(parse_string, Literal::string),
+        parse_date_time,
+        map(parse_regex, Literal::Regex),
+        map(parse_duration, Literal::Duration),
         map(parse_number, Literal::Number),
     ))(input)
 }
@@ -53,6 +63,29 @@ mod tests {
         assert_eq!(parse_literal("false"), Ok(("", Literal::Boolean(false))));
     }
 
+    #[test]
+    fn test_parse_null_literal() {
+        assert_eq!(parse_literal("null)"), Ok((")", Literal::Null)));
+        assert!(parse_literal("nullable").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_literals() {
+        assert!(matches!(
+            parse_literal("@2024-01-31"),
+            Ok(("", Literal::Date(_)))
+        ));
+        assert!(matches!(
+            parse_literal("@2024-01-31T12:00:00Z"),
+            Ok(("", Literal::Timestamp(_)))
+        ));
+        assert_eq!(
+            parse_literal("90s"),
+            Ok(("", Literal::Duration(chrono::TimeDelta::seconds(90))))
+        );
+        assert_eq!(parse_literal("90 s"), Ok((" s", Literal::Number(n("90")))));
+    }
+
     #[test]
     fn test_parse_symbol_literal() {
         assert_eq!(
@@ -4,21 +4,369 @@ mod boolean;
 mod expr;
 mod function;
 mod identifier;
+mod lambda;
+mod lexer;
 mod literal;
 mod number;
 mod operands;
+mod pattern;
+mod program;
+mod regex;
+mod source;
 mod string;
 mod symbol;
+mod time;
+mod trivia;
+
+use expr::ExprParser;
+use nom::error::VerboseError;
+use source::Source;
+
+use crate::Error;
 
 pub use crate::ast::BinaryOp;
 pub use crate::ast::Expr;
+pub use crate::ast::ExprKind;
 pub use crate::ast::Literal;
+pub use crate::ast::Program;
+pub use crate::ast::Statement;
+pub use crate::ast::StatementKind;
 pub use crate::ast::UnaryOp;
 pub use expr::parse_expr;
 pub use identifier::parse_identifier;
+pub use identifier::parse_name;
+pub use identifier::parse_quoted_identifier;
+pub use lexer::tokenize;
+pub use lexer::Token;
+pub use lexer::TokenKind;
+pub use lexer::KEYWORDS;
 pub use literal::parse_literal;
 pub use number::parse_number;
 pub use operands::parse_binary_op;
 pub use operands::parse_unary_op;
+pub use regex::parse_regex;
 pub use string::parse_string;
 pub use symbol::parse_symbol;
+pub use symbol::parse_symbol_name;
+pub use time::parse_date_time;
+pub use time::parse_duration;
+pub use trivia::parse_comment;
+pub use trivia::parse_trivia;
+
+/// The result type of every parser in this module: nom's `IResult` with a [`VerboseError`],
+/// which keeps enough context (expected characters and what was being parsed) to report
+/// "expected X, found Y".
+pub type IResult<I, O> = nom::IResult<I, O, VerboseError<I>>;
+
+/// Parses the whole of `input` as a single expression.
+///
+/// Unlike [`parse_expr`], trailing input is an error, and failures are reported as a
+/// located [`Error::ParserError`] (or [`Error::SyntaxError`] for malformed tokens) of the
+/// form "expected X, found Y at line L, column C" followed by a caret snippet of the line.
+/// The input is split into tokens, as by [`tokenize`], before it is parsed, so a lexical error,
+/// such as an unterminated string, is reported as an [`Error::LexerError`].
+pub fn parse(input: &str) -> Result<Expr, Error> {
+    parse_all(input, ExprParser::parse_expr)
+}
+
+/// Parses the whole of `input` as a [`Program`]: statements separated by newlines or `;`,
+/// such as `let` bindings, ending with the expression whose value is the result.
+/// Errors are reported as by [`parse`].
+pub fn parse_program(input: &str) -> Result<Program, Error> {
+    parse_all(input, ExprParser::parse_program)
+}
+
+fn parse_all<'s, T>(
+    input: &'s str,
+    parse: impl Fn(&ExprParser<'s>, &'s str) -> IResult<&'s str, T>,
+) -> Result<T, Error> {
+    let (tokens, lexed) = lexer::lex(input);
+    lexed?;
+    let parser = ExprParser::with_tokens(input, tokens);
+    match parse(&parser, input) {
+        Ok((rest, _)) if !rest.is_empty() => {
+            let message = format!("expected end of input, found {}", Source::found(rest));
+            Err(Error::ParserError(parser.source().located(message, rest)))
+        }
+        Ok((_, parsed)) => Ok(parsed),
+        Err(e) => Err(parser.source().error(e)),
+    }
+}
+
+/// Converts an error returned by [`parse_expr`] into a located [`Error`], as [`parse`] reports it.
+/// `source` must be the text that was passed to `parse_expr`.
+pub fn located_error(source: &str, err: nom::Err<VerboseError<&str>>) -> Error {
+    Source::new(source).error(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_err(input: &str) -> Error {
+        parse(input).expect_err("parse should fail")
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(" 1 + 2 ").unwrap(),
+            Expr::binary_expr(
+                Expr::literal_number(1),
+                BinaryOp::Add,
+                Expr::literal_number(2)
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_deep_nesting() {
+        for input in ["(".repeat(10_000), "[".repeat(10_000), "-".repeat(10_000)] {
+            assert!(matches!(
+                parse_err(&input),
+                Error::ParserError(message) if message.starts_with("nesting too deep at line 1")
+            ));
+        }
+        assert!(matches!(
+            parse_program(&format!("match x {{ {} }}", "[".repeat(10_000))),
+            Err(Error::ParserError(message)) if message.starts_with("nesting too deep")
+        ));
+        let depth = 250;
+        let nested = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse(&nested).is_ok());
+        let nested = format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested).is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_trailing_input() {
+        assert!(matches!(
+            parse_err("1 + 2 )"),
+            Error::ParserError(message)
+                if message.starts_with("expected end of input, found ')' at line 1, column 7")
+        ));
+    }
+
+    #[test]
+    fn test_parse_missing_operand() {
+        assert!(matches!(
+            parse_err("1 +"),
+            Error::ParserError(message)
+                if message.starts_with("expected expression, found end of input at line 1, column 4")
+        ));
+    }
+
+    #[test]
+    fn test_parse_unclosed_delimiters() {
+        assert!(matches!(
+            parse_err("(1 + 2"),
+            Error::ParserError(message)
+                if message.starts_with("expected ')', found end of input")
+        ));
+        assert!(matches!(
+            parse_err("add(1, )"),
+            Error::ParserError(message)
+                if message.starts_with("expected ')', found ',' at line 1, column 6")
+        ));
+    }
+
+    #[test]
+    fn test_parse_readme_example() {
+        let input = r#"READ("fixtures/table.parquet") |>
+HEAD(10) |>
+SELECT("two", "four") |>
+WRITE("test.json", pretty: true)"#;
+        let expr = parse(input).unwrap();
+        let ExprKind::BinaryExpr(_, BinaryOp::Pipe, write) = &expr.kind else {
+            panic!("expected a pipeline, got {:?}", expr);
+        };
+        assert_eq!(
+            **write,
+            Expr::function_call_named(
+                "WRITE",
+                vec![Expr::literal_string("test.json")],
+                vec![("pretty", Expr::literal_boolean(true))]
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_duplicate_named_argument() {
+        assert!(matches!(
+            parse_err("f(pretty: true, pretty: false)"),
+            Error::ParserError(message)
+                if message.starts_with("expected unique argument name, found 'pretty' at line 1, column 17")
+        ));
+    }
+
+    #[test]
+    fn test_parse_program() {
+        let program = parse_program("let x = 1; let y = 2\nx + y\n").unwrap();
+        assert_eq!(
+            program.statements.last(),
+            Some(&Statement::expr(Expr::binary_expr(
+                Expr::ident("x"),
+                BinaryOp::Add,
+                Expr::ident("y")
+            )))
+        );
+        assert_eq!(program.statements.len(), 3);
+        assert!(matches!(
+            parse_program("let x = 1 2"),
+            Err(Error::ParserError(message))
+                if message.starts_with("expected end of input, found '2' at line 1, column 11")
+        ));
+        assert!(matches!(
+            parse_program("let \"a\" = x"),
+            Err(Error::ParserError(message))
+                if message.starts_with("expected identifier, found '\"' at line 1, column 5")
+        ));
+    }
+
+    #[test]
+    fn test_parse_reserved_binding_names() {
+        for (input, column) in [
+            ("let true = 1; true", 5),
+            ("let null = 1", 5),
+            ("let if = 1", 5),
+            ("let in = 1; in", 5),
+            ("def f(a, null) = a", 10),
+            ("fn(match) => 1", 4),
+            ("match x { then => 1 }", 11),
+            ("match x { [a, ..else] => a }", 17),
+        ] {
+            assert!(
+                matches!(
+                    parse_program(input),
+                    Err(Error::SyntaxError(message))
+                        if message.starts_with(&format!(
+                            "expected name that is not a reserved word, found '{}' at line 1, column {}",
+                            input[column - 1..].split(|c: char| !c.is_alphabetic()).next().unwrap(),
+                            column
+                        ))
+                ),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            parse_program("let `true` = 1; `true`")
+                .unwrap()
+                .statements
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_parse_names_starting_with_literal_words() {
+        let program = parse_program(
+            "let trueish = 1; let falsey = 2; let nullable = 3\ntrueish + falsey + nullable",
+        )
+        .unwrap();
+        assert_eq!(
+            program.statements.last(),
+            Some(&Statement::expr(Expr::binary_expr(
+                Expr::binary_expr(Expr::ident("trueish"), BinaryOp::Add, Expr::ident("falsey")),
+                BinaryOp::Add,
+                Expr::ident("nullable")
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parse_reserved_words_as_variables() {
+        for input in ["match + 1", "then", "fn(1)", "x |> match", "in"] {
+            assert!(parse(input).is_err(), "{input}");
+        }
+        assert!(parse("`match` + `fn`(1)").is_ok());
+    }
+
+    #[test]
+    fn test_parse_reserved_words_as_keys() {
+        assert_eq!(
+            parse("{if: 1, in:x}.in").unwrap(),
+            Expr::field(
+                Expr::record(vec![
+                    ("if", Expr::literal_number(1)),
+                    ("in", Expr::ident("x"))
+                ]),
+                "in"
+            )
+        );
+        assert_eq!(
+            parse("f(match:x)").unwrap(),
+            Expr::function_call_named("f", vec![], vec![("match", Expr::ident("x"))])
+        );
+        assert_eq!(
+            parse("[if :a == x then :b else :c]").unwrap(),
+            Expr::list(vec![Expr::if_else(
+                Expr::binary_expr(Expr::literal_symbol("a"), BinaryOp::Eq, Expr::ident("x")),
+                Expr::literal_symbol("b"),
+                Expr::literal_symbol("c")
+            )])
+        );
+    }
+
+    #[test]
+    fn test_parse_symbol_at_start_of_line() {
+        let input = "let status = x\n:ok";
+        assert_eq!(parse_program(input).unwrap().statements.len(), 2);
+        assert_eq!(
+            tokenize(input).unwrap().last().map(|t| t.kind),
+            Some(TokenKind::Symbol)
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_regex() {
+        assert!(matches!(
+            parse_err("level == :error &&\n  line =~ ~r\"[0-9\""),
+            Error::SyntaxError(message)
+                if message.starts_with("expected regular expression, found 'r' at line 2, column 12")
+        ));
+    }
+
+    #[test]
+    fn test_parse_invalid_escape() {
+        assert!(matches!(
+            parse_err(r#"x + "a\qb""#),
+            Error::SyntaxError(message)
+                if message.starts_with("expected escape sequence, found 'qb' at line 1, column 8")
+        ));
+        assert_eq!(
+            parse("r + 1").unwrap(),
+            Expr::binary_expr(Expr::ident("r"), BinaryOp::Add, Expr::literal_number(1))
+        );
+    }
+
+    #[test]
+    fn test_parse_exponent_out_of_range() {
+        assert!(matches!(
+            parse_err("1e10000000 + 0.1"),
+            Error::SyntaxError(message)
+                if message.starts_with("expected exponent between -10000 and 10000, found '1e10000000' at line 1, column 1")
+        ));
+    }
+
+    #[test]
+    fn test_parse_lexer_error() {
+        assert!(matches!(
+            parse_err("1 + \"abc"),
+            Error::LexerError(message)
+                if message.starts_with("unterminated string at line 1, column 5")
+        ));
+        assert!(matches!(
+            parse_program("let x = 1\nx ? 2"),
+            Err(Error::LexerError(message))
+                if message.starts_with("unexpected character '?' at line 2, column 3")
+        ));
+    }
+
+    #[test]
+    fn test_parse_empty_input() {
+        assert!(matches!(
+            parse_err(""),
+            Error::ParserError(message) if message.starts_with("expected expression, found end of input")
+        ));
+    }
+}
@@ -1,32 +1,121 @@
 use std::str::FromStr;
 
+use bigdecimal::num_bigint::BigInt;
 use bigdecimal::BigDecimal;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::digit0;
+use nom::bytes::complete::take_while;
+use nom::character::complete::char;
 use nom::character::complete::digit1;
+use nom::combinator::cut;
 use nom::combinator::map_res;
+use nom::combinator::not;
 use nom::combinator::opt;
 use nom::combinator::recognize;
+use nom::error::context;
+use nom::error::ErrorKind;
+use nom::error::ParseError;
+use nom::error::VerboseError;
+use nom::error::VerboseErrorKind;
+use nom::multi::many0;
+use nom::sequence::preceded;
 use nom::sequence::tuple;
-use nom::IResult;
+use nom::Offset;
 
+use super::IResult;
 use crate::ast::Numeric;
 
-/// Parses a numeric: optional `+` or `-`, digits, then optionally `.` followed by any number of decimal digits.
+/// Parses a numeric: optional `+` or `-`, then either
+/// - a decimal: digits, optionally `.` followed by any number of decimal digits, then optionally
+///   an exponent (`e` or `E`, an optional sign, and digits), as in `2.5E-3`. The exponent must be
+///   between -10000 and 10000; or
+/// - an integer in another base: `0x` (hexadecimal), `0b` (binary) or `0o` (octal) followed by
+///   digits in that base, as in `0xFF`.
+///
+/// Digits may be separated by single underscores (`1_000_000`, `0xFF_FF`). Values are exact.
 pub fn parse_number(input: &str) -> IResult<&str, Numeric> {
-    map_res(
-        recognize(tuple((
-            opt(alt((tag("-"), tag("+")))),
-            digit1,
-            opt(tuple((tag("."), digit0))),
-        ))),
-        |s: &str| {
-            BigDecimal::from_str(s)
-                .map(Numeric::new)
-                .map_err(|e| format!("invalid number '{}': {}", s, e))
-        },
-    )(input)
+    let (rest, sign) = opt(alt((tag("-"), tag("+"))))(input)?;
+    let (rest, value) = alt((
+        parse_radix("0x", 16, "hexadecimal digits"),
+        parse_radix("0b", 2, "binary digits"),
+        parse_radix("0o", 8, "octal digits"),
+        parse_decimal,
+    ))(rest)?;
+    let value = match sign {
+        Some("-") => -value,
+        _ => value,
+    };
+    Ok((rest, Numeric::new(value)))
+}
+
+/// Parses digits separated by single underscores.
+pub(super) fn digits(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((digit1, many0(tuple((char('_'), digit1))))))(input)
+}
+
+/// The largest exponent a decimal may have, either way. Beyond it, arithmetic on the value would
+/// take too long (`1e10000000 + 0.1` has ten million digits).
+const MAX_EXPONENT: i64 = 10_000;
+
+fn parse_decimal(input: &str) -> IResult<&str, BigDecimal> {
+    let (rest, (_, _, exponent)) = tuple((
+        digits,
+        // A `.` followed by another is a range (`1..5`), not a decimal point.
+        opt(tuple((tag("."), not(char('.')), opt(digits)))),
+        opt(preceded(
+            alt((tag("e"), tag("E"))),
+            recognize(tuple((opt(alt((tag("-"), tag("+")))), digits))),
+        )),
+    ))(input)?;
+    let in_range = exponent.is_none_or(|e| {
+        e.replace('_', "")
+            .parse::<i64>()
+            .is_ok_and(|e| e.abs() <= MAX_EXPONENT)
+    });
+    if !in_range {
+        return Err(nom::Err::Failure(VerboseError {
+            errors: vec![
+                (input, VerboseErrorKind::Nom(ErrorKind::MapRes)),
+                (
+                    input,
+                    VerboseErrorKind::Context("exponent between -10000 and 10000"),
+                ),
+            ],
+        }));
+    }
+    let literal = &input[..input.offset(rest)];
+    let value = BigDecimal::from_str(&literal.replace('_', ""))
+        .map_err(|_| nom::Err::Error(VerboseError::from_error_kind(input, ErrorKind::MapRes)))?;
+    Ok((rest, value))
+}
+
+/// Parses `prefix` followed by digits in base `radix`. Once the prefix is seen, anything else
+/// is a failure.
+fn parse_radix<'a>(
+    prefix: &'static str,
+    radix: u32,
+    expected: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, BigDecimal> {
+    preceded(
+        tag(prefix),
+        cut(context(
+            expected,
+            map_res(
+                take_while(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+                move |s: &str| {
+                    let valid = !s.is_empty()
+                        && !s.starts_with('_')
+                        && !s.ends_with('_')
+                        && !s.contains("__");
+                    valid
+                        .then(|| BigInt::parse_bytes(s.replace('_', "").as_bytes(), radix))
+                        .flatten()
+                        .map(BigDecimal::from)
+                        .ok_or_else(|| format!("invalid {} '{}'", expected, s))
+                },
+            ),
+        )),
+    )
 }
 
 #[cfg(test)]
@@ -34,6 +123,8 @@ mod tests {
     use std::str::FromStr;
 
     use bigdecimal::BigDecimal;
+    use nom::error::ErrorKind;
+    use nom::error::VerboseErrorKind;
 
     use crate::ast::Numeric;
 
@@ -77,6 +168,12 @@ mod tests {
         assert_eq!(parse_number("-7."), Ok(("", n("-7"))));
     }
 
+    #[test]
+    fn test_parse_number_before_range() {
+        assert_eq!(parse_number("1..5"), Ok(("..5", n("1"))));
+        assert_eq!(parse_number("1.5..=2"), Ok(("..=2", n("1.5"))));
+    }
+
     #[test]
     fn test_parse_positive_decimal() {
         assert_eq!(parse_number("+3.14"), Ok(("", n("3.14"))));
@@ -89,6 +186,65 @@ mod tests {
         assert_eq!(parse_number("-0.5"), Ok(("", n("-0.5"))));
     }
 
+    #[test]
+    fn test_parse_exponent() {
+        assert_eq!(parse_number("1e6"), Ok(("", n("1000000"))));
+        assert_eq!(parse_number("2.5E-3"), Ok(("", n("0.0025"))));
+        assert_eq!(parse_number("-1.5e+2"), Ok(("", n("-150"))));
+        assert_eq!(parse_number("2else"), Ok(("else", n("2"))));
+        assert_eq!(parse_number("1e10_000"), Ok(("", n("1e10000"))));
+        assert_eq!(parse_number("-1E-10000"), Ok(("", n("-1e-10000"))));
+    }
+
+    #[test]
+    fn test_parse_exponent_out_of_range() {
+        for input in [
+            "1e10001",
+            "2.5E-10001",
+            "1e10000000",
+            "1e99999999999999999999",
+        ] {
+            let Err(nom::Err::Failure(e)) = parse_number(input) else {
+                panic!("{} should fail", input);
+            };
+            assert_eq!(
+                e.errors,
+                vec![
+                    (input, VerboseErrorKind::Nom(ErrorKind::MapRes)),
+                    (
+                        input,
+                        VerboseErrorKind::Context("exponent between -10000 and 10000")
+                    ),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_radix() {
+        assert_eq!(parse_number("0xFF"), Ok(("", n("255"))));
+        assert_eq!(parse_number("0xff_ff"), Ok(("", n("65535"))));
+        assert_eq!(parse_number("-0b1010"), Ok(("", n("-10"))));
+        assert_eq!(parse_number("0o17 "), Ok((" ", n("15"))));
+        for input in ["0x", "0xFG", "0b102", "0x_1", "0b1__0"] {
+            let Err(nom::Err::Failure(e)) = parse_number(input) else {
+                panic!("{} should fail", input);
+            };
+            assert_eq!(
+                e.errors[0],
+                (&input[2..], VerboseErrorKind::Nom(ErrorKind::MapRes))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_digit_separators() {
+        assert_eq!(parse_number("1_000_000"), Ok(("", n("1000000"))));
+        assert_eq!(parse_number("1_000.000_5"), Ok(("", n("1000.0005"))));
+        assert_eq!(parse_number("1__0"), Ok(("__0", n("1"))));
+        assert_eq!(parse_number("1_"), Ok(("_", n("1"))));
+    }
+
     #[test]
     fn test_parse_number_with_remainder() {
         assert_eq!(parse_number("42 "), Ok((" ", n("42"))));
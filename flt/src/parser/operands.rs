@@ -1,8 +1,11 @@
 use nom::branch::alt;
 use nom::bytes::complete::tag;
+use nom::combinator::not;
 use nom::combinator::value;
-use nom::IResult;
+use nom::sequence::terminated;
 
+use super::identifier::parse_identifier;
+use super::IResult;
 use crate::ast::BinaryOp;
 use crate::ast::UnaryOp;
 
@@ -15,20 +18,39 @@ pub fn parse_unary_op(input: &str) -> IResult<&str, UnaryOp> {
     ))(input)
 }
 
-/// Parses a binary operand. Longer tokens must be tried first (`&&` before `&`, `||` before `|`, `^^` before `^`, `|>` before `|`).
+/// Parses a binary operand. Longer tokens must be tried first (`&&` before `&`, `||` before `|`, `^^` before `^`, `|>` before `|`, `<=` before `<`, `>=` before `>`, `**` before `*`, `//` before `/`).
+/// The word `in` must not run on into an identifier, so `index` is not `in` followed by `dex`.
 pub fn parse_binary_op(input: &str) -> IResult<&str, BinaryOp> {
+    // Two-character operators, then single-character ones.
     alt((
-        value(BinaryOp::Pipe, tag("|>")),
-        value(BinaryOp::And, tag("&&")),
-        value(BinaryOp::Or, tag("||")),
-        value(BinaryOp::Xor, tag("^^")),
-        value(BinaryOp::BitAnd, tag("&")),
-        value(BinaryOp::BitOr, tag("|")),
-        value(BinaryOp::BitXor, tag("^")),
-        value(BinaryOp::Mul, tag("*")),
-        value(BinaryOp::Add, tag("+")),
-        value(BinaryOp::Div, tag("/")),
-        value(BinaryOp::Sub, tag("-")),
+        alt((
+            value(BinaryOp::In, terminated(tag("in"), not(parse_identifier))),
+            value(BinaryOp::Pipe, tag("|>")),
+            value(BinaryOp::Coalesce, tag("??")),
+            value(BinaryOp::And, tag("&&")),
+            value(BinaryOp::Or, tag("||")),
+            value(BinaryOp::Xor, tag("^^")),
+            value(BinaryOp::Eq, tag("==")),
+            value(BinaryOp::Ne, tag("!=")),
+            value(BinaryOp::Match, tag("=~")),
+            value(BinaryOp::NotMatch, tag("!~")),
+            value(BinaryOp::Le, tag("<=")),
+            value(BinaryOp::Ge, tag(">=")),
+            value(BinaryOp::Pow, tag("**")),
+            value(BinaryOp::IntDiv, tag("//")),
+        )),
+        alt((
+            value(BinaryOp::Lt, tag("<")),
+            value(BinaryOp::Gt, tag(">")),
+            value(BinaryOp::BitAnd, tag("&")),
+            value(BinaryOp::BitOr, tag("|")),
+            value(BinaryOp::BitXor, tag("^")),
+            value(BinaryOp::Rem, tag("%")),
+            value(BinaryOp::Mul, tag("*")),
+            value(BinaryOp::Add, tag("+")),
+            value(BinaryOp::Div, tag("/")),
+            value(BinaryOp::Sub, tag("-")),
+        )),
     ))(input)
 }
 
@@ -58,6 +80,9 @@ mod tests {
         assert_eq!(parse_binary_op("&"), Ok(("", BinaryOp::BitAnd)));
         assert_eq!(parse_binary_op("|"), Ok(("", BinaryOp::BitOr)));
         assert_eq!(parse_binary_op("^"), Ok(("", BinaryOp::BitXor)));
+        assert_eq!(parse_binary_op("<"), Ok(("", BinaryOp::Lt)));
+        assert_eq!(parse_binary_op(">"), Ok(("", BinaryOp::Gt)));
+        assert_eq!(parse_binary_op("%"), Ok(("", BinaryOp::Rem)));
     }
 
     #[test]
@@ -66,6 +91,24 @@ mod tests {
         assert_eq!(parse_binary_op("||"), Ok(("", BinaryOp::Or)));
         assert_eq!(parse_binary_op("^^"), Ok(("", BinaryOp::Xor)));
         assert_eq!(parse_binary_op("|>"), Ok(("", BinaryOp::Pipe)));
+        assert_eq!(parse_binary_op("??"), Ok(("", BinaryOp::Coalesce)));
+        assert_eq!(parse_binary_op("=="), Ok(("", BinaryOp::Eq)));
+        assert_eq!(parse_binary_op("!="), Ok(("", BinaryOp::Ne)));
+        assert_eq!(parse_binary_op("=~"), Ok(("", BinaryOp::Match)));
+        assert_eq!(parse_binary_op("!~"), Ok(("", BinaryOp::NotMatch)));
+        assert_eq!(parse_binary_op("<="), Ok(("", BinaryOp::Le)));
+        assert_eq!(parse_binary_op(">="), Ok(("", BinaryOp::Ge)));
+        assert_eq!(parse_binary_op("**"), Ok(("", BinaryOp::Pow)));
+        assert_eq!(parse_binary_op("//"), Ok(("", BinaryOp::IntDiv)));
+        assert_eq!(parse_binary_op("in"), Ok(("", BinaryOp::In)));
+    }
+
+    #[test]
+    fn test_parse_binary_op_in_is_a_word() {
+        assert_eq!(parse_binary_op("in xs"), Ok((" xs", BinaryOp::In)));
+        assert_eq!(parse_binary_op("in[1]"), Ok(("[1]", BinaryOp::In)));
+        assert!(parse_binary_op("index").is_err());
+        assert!(parse_binary_op("in_stock").is_err());
     }
 
     #[test]
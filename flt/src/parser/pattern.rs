@@ -0,0 +1,236 @@
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::cut;
+use nom::combinator::map;
+use nom::combinator::opt;
+use nom::error::context;
+use nom::error::VerboseError;
+use nom::error::VerboseErrorKind;
+use nom::multi::separated_list0;
+use nom::sequence::delimited;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
+use nom::sequence::tuple;
+
+use super::expr::ExprParser;
+use super::expr::LITERALS;
+use super::lexer::TokenKind;
+use super::literal::parse_literal;
+use super::IResult;
+use crate::ast::Identifier;
+use crate::ast::Literal;
+use crate::ast::Pattern;
+
+impl<'s> ExprParser<'s> {
+    /// Parses a `match` pattern: a literal, `_`, a binding, a list pattern or a record pattern.
+    /// A name may be bound only once in a pattern.
+    pub(super) fn parse_pattern(&self, input: &'s str) -> IResult<&'s str, Pattern> {
+        let (rest, pattern) = self.parse_subpattern(input)?;
+        let bindings = pattern.bindings();
+        for (i, name) in bindings.iter().enumerate() {
+            if bindings[..i].iter().any(|b| b.name == name.name) {
+                let at = &self.source().text()[name.span.start..];
+                return Err(nom::Err::Failure(VerboseError {
+                    errors: vec![(at, VerboseErrorKind::Context("unique binding name"))],
+                }));
+            }
+        }
+        Ok((rest, pattern))
+    }
+
+    fn parse_subpattern(&self, input: &'s str) -> IResult<&'s str, Pattern> {
+        self.nested(input, |input| {
+            context(
+                "pattern",
+                alt((
+                    map(|i| self.parse_literal_pattern(i), Pattern::Literal),
+                    |i| self.parse_list_pattern(i),
+                    |i| self.parse_record_pattern(i),
+                    map(|i| self.parse_binding_name(i), |name| self.binding(name)),
+                )),
+            )(input)
+        })
+    }
+
+    /// Parses a literal. A number may be negative, as in `-1`, with its sign directly before it.
+    fn parse_literal_pattern(&self, input: &'s str) -> IResult<&'s str, Literal> {
+        alt((self.lexeme(LITERALS, parse_literal), |input| {
+            let (number, _) = self.lexeme(&[TokenKind::Operator], tag("-"))(input)?;
+            self.lexeme(&[TokenKind::Number], |_| parse_literal(input))(number)
+        }))(input)
+    }
+
+    /// Parses `[` Pattern* `]`, where the last item may be a rest pattern: `..name` or `..`.
+    fn parse_list_pattern(&self, input: &'s str) -> IResult<&'s str, Pattern> {
+        let item = |input: &'s str| {
+            let (rest, dots) = opt(self.punct(".."))(input)?;
+            let (rest, pattern) = match dots {
+                Some(_) => map(opt(|i| self.parse_binding_name(i)), |name| match name {
+                    Some(name) => self.binding(name),
+                    None => Pattern::Wildcard,
+                })(rest)?,
+                None => self.parse_subpattern(rest)?,
+            };
+            Ok((rest, (input, dots.is_some(), pattern)))
+        };
+        let (rest, items) = preceded(
+            self.punct("["),
+            cut(terminated(
+                delimited(
+                    |i| self.ws(i),
+                    separated_list0(
+                        tuple((|i| self.ws(i), self.char(','), |i| self.ws(i))),
+                        item,
+                    ),
+                    |i| self.ws(i),
+                ),
+                self.char(']'),
+            )),
+        )(input)?;
+
+        let mut patterns = vec![];
+        let mut rest_pattern = None;
+        for (at, is_rest, pattern) in items {
+            if rest_pattern.is_some() {
+                return Err(nom::Err::Failure(VerboseError {
+                    errors: vec![(at, VerboseErrorKind::Context("rest pattern last"))],
+                }));
+            }
+            if is_rest {
+                rest_pattern = Some(Box::new(pattern));
+            } else {
+                patterns.push(pattern);
+            }
+        }
+        Ok((rest, Pattern::List(patterns, rest_pattern)))
+    }
+
+    /// Parses `{` (key (`:` Pattern)?)* `}`. A key on its own binds the field to that name.
+    fn parse_record_pattern(&self, input: &'s str) -> IResult<&'s str, Pattern> {
+        let field = |input: &'s str| {
+            let (rest, key) = self.parse_key(input)?;
+            let (rest, pattern) = opt(preceded(
+                tuple((|i| self.ws(i), self.char(':'), |i| self.ws(i))),
+                cut(|i| self.parse_subpattern(i)),
+            ))(rest)?;
+            let pattern = pattern.unwrap_or_else(|| Pattern::Binding(key.clone()));
+            Ok((rest, (input, key, pattern)))
+        };
+        let (rest, fields) = preceded(
+            self.punct("{"),
+            cut(terminated(
+                delimited(
+                    |i| self.ws(i),
+                    separated_list0(
+                        tuple((|i| self.ws(i), self.char(','), |i| self.ws(i))),
+                        field,
+                    ),
+                    |i| self.ws(i),
+                ),
+                self.char('}'),
+            )),
+        )(input)?;
+
+        let mut record: Vec<(Identifier, Pattern)> = vec![];
+        for (at, key, pattern) in fields {
+            if record.iter().any(|(k, _)| k.name == key.name) {
+                return Err(nom::Err::Failure(VerboseError {
+                    errors: vec![(at, VerboseErrorKind::Context("unique field name"))],
+                }));
+            }
+            record.push((key, pattern));
+        }
+        Ok((rest, Pattern::Record(record)))
+    }
+
+    /// `_` is the wildcard; any other name is a binding.
+    fn binding(&self, name: &'s str) -> Pattern {
+        match name {
+            "_" => Pattern::Wildcard,
+            _ => Pattern::Binding(self.identifier(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_pattern(input: &str) -> IResult<&str, Pattern> {
+        ExprParser::new(input).parse_pattern(input)
+    }
+
+    #[test]
+    fn test_parse_literal_patterns() {
+        assert_eq!(
+            parse_pattern(":ok"),
+            Ok(("", Pattern::Literal(Literal::symbol("ok"))))
+        );
+        assert_eq!(
+            parse_pattern("-1"),
+            Ok(("", Pattern::Literal(Literal::number(-1))))
+        );
+        assert_eq!(parse_pattern("_"), Ok(("", Pattern::Wildcard)));
+        assert_eq!(parse_pattern("_x"), Ok(("", Pattern::binding("_x"))));
+    }
+
+    #[test]
+    fn test_parse_list_pattern() {
+        assert_eq!(
+            parse_pattern("[first, _, ..rest]"),
+            Ok((
+                "",
+                Pattern::List(
+                    vec![Pattern::binding("first"), Pattern::Wildcard],
+                    Some(Box::new(Pattern::binding("rest")))
+                )
+            ))
+        );
+        assert_eq!(
+            parse_pattern("[ .. ]"),
+            Ok(("", Pattern::List(vec![], Some(Box::new(Pattern::Wildcard)))))
+        );
+        assert_eq!(parse_pattern("[]"), Ok(("", Pattern::List(vec![], None))));
+        let input = "[..rest, x]";
+        let Err(nom::Err::Failure(e)) = parse_pattern(input) else {
+            panic!("a rest pattern before the end should fail");
+        };
+        assert_eq!(
+            e.errors[0],
+            (&input[9..], VerboseErrorKind::Context("rest pattern last"))
+        );
+    }
+
+    #[test]
+    fn test_parse_record_pattern() {
+        assert_eq!(
+            parse_pattern(r#"{name, "order id": [id], `status`: :paid}"#),
+            Ok((
+                "",
+                Pattern::record(vec![
+                    ("name", Pattern::binding("name")),
+                    (
+                        "order id",
+                        Pattern::List(vec![Pattern::binding("id")], None)
+                    ),
+                    ("status", Pattern::Literal(Literal::symbol("paid"))),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_duplicate_binding() {
+        let input = "[x, {a: x}]";
+        let Err(nom::Err::Failure(e)) = parse_pattern(input) else {
+            panic!("binding a name twice should fail");
+        };
+        assert_eq!(
+            e.errors,
+            vec![(
+                &input[8..],
+                VerboseErrorKind::Context("unique binding name")
+            )]
+        );
+    }
+}
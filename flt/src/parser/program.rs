@@ -0,0 +1,367 @@
+use nom::branch::alt;
+use nom::combinator::cut;
+use nom::combinator::opt;
+use nom::combinator::recognize;
+use nom::combinator::value;
+use nom::combinator::verify;
+use nom::error::context;
+use nom::multi::separated_list1;
+use nom::sequence::tuple;
+
+use super::expr::ExprParser;
+use super::IResult;
+use crate::ast::Program;
+use crate::ast::Statement;
+use crate::ast::StatementKind;
+
+impl<'s> ExprParser<'s> {
+    /// Parses a program: one or more statements separated by newlines or `;`, optionally
+    /// ending with `;`. A line that starts with a binary operator (such as `|>`) continues
+    /// the statement on the line before it, unless the operator is `-` or `+`, which start a new
+    /// statement there as unary operators.
+    pub fn parse_program(&self, input: &'s str) -> IResult<&'s str, Program> {
+        self.start_statements();
+        let (input, _) = self.ws(input)?;
+        let (input, mut statements) =
+            separated_list1(|i| self.parse_separator(i), |i| self.parse_statement(i))(input)?;
+        let (input, _) = tuple((|i| self.ws(i), opt(self.punct(";")), |i| self.ws(i)))(input)?;
+        self.attach_statement_comments(&mut statements, input);
+        Ok((input, Program::new(statements)))
+    }
+
+    /// Parses the end of a statement: `;`, or whitespace containing a line break.
+    fn parse_separator(&self, input: &'s str) -> IResult<&'s str, ()> {
+        alt((
+            value((), tuple((|i| self.ws(i), self.punct(";"), |i| self.ws(i)))),
+            value(
+                (),
+                verify(recognize(|i| self.ws(i)), |s: &str| s.contains('\n')),
+            ),
+        ))(input)
+    }
+
+    /// Parses a statement: a `let` binding, a function definition, or an expression.
+    fn parse_statement(&self, input: &'s str) -> IResult<&'s str, Statement> {
+        alt((
+            |i| self.parse_let(i),
+            |i| self.parse_def(i),
+            |i| {
+                let (rest, expr) = self.parse_pipe(i)?;
+                let span = expr.span;
+                Ok((rest, Statement::new(StatementKind::Expr(expr), span)))
+            },
+        ))(input)
+    }
+
+    /// Parses `let name = expr`. Once `let` has been seen the statement must be a binding.
+    fn parse_let(&self, input: &'s str) -> IResult<&'s str, Statement> {
+        let (rest, _) = self.keyword("let")(input)?;
+        let (rest, (_, name, _, _, _, value)) = cut(tuple((
+            |i| self.ws(i),
+            context("identifier", |i| self.parse_binding_name(i)),
+            |i| self.ws(i),
+            self.char('='),
+            |i| self.ws(i),
+            |i| self.parse_pipe(i),
+        )))(rest)?;
+        let kind = StatementKind::Let(self.identifier(name), value);
+        Ok((rest, Statement::new(kind, self.source().span(input, rest))))
+    }
+
+    /// Parses `def name(params) = expr`. Once `def` has been seen the statement must be a definition.
+    fn parse_def(&self, input: &'s str) -> IResult<&'s str, Statement> {
+        let (rest, _) = self.keyword("def")(input)?;
+        let (rest, (_, name, _, _, params, _, _, _, body)) = cut(tuple((
+            |i| self.ws(i),
+            context("identifier", |i| self.parse_binding_name(i)),
+            |i| self.ws(i),
+            self.char('('),
+            |i| self.parse_parameters(i),
+            |i| self.ws(i),
+            self.char('='),
+            |i| self.ws(i),
+            |i| self.parse_pipe(i),
+        )))(rest)?;
+        let kind = StatementKind::Def(self.identifier(name), params, body);
+        Ok((rest, Statement::new(kind, self.source().span(input, rest))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::BinaryOp;
+    use crate::ast::Expr;
+    use crate::ast::ExprKind;
+    use crate::ast::UnaryOp;
+
+    fn parse_program(input: &str) -> IResult<&str, Program> {
+        ExprParser::new(input).parse_program(input)
+    }
+
+    #[test]
+    fn test_parse_statements() {
+        assert_eq!(
+            parse_program("let x = 1\nlet y = x + 1; y * 2;"),
+            Ok((
+                "",
+                Program::new(vec![
+                    Statement::let_binding("x", Expr::literal_number(1)),
+                    Statement::let_binding(
+                        "y",
+                        Expr::binary_expr(Expr::ident("x"), BinaryOp::Add, Expr::literal_number(1))
+                    ),
+                    Statement::expr(Expr::binary_expr(
+                        Expr::ident("y"),
+                        BinaryOp::Mul,
+                        Expr::literal_number(2)
+                    )),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_continuation_lines() {
+        assert_eq!(
+            parse_program("let rows = input\n  |> trim\n\n  |> upper\nrows"),
+            Ok((
+                "",
+                Program::new(vec![
+                    Statement::let_binding(
+                        "rows",
+                        Expr::binary_expr(
+                            Expr::binary_expr(
+                                Expr::ident("input"),
+                                BinaryOp::Pipe,
+                                Expr::ident("trim")
+                            ),
+                            BinaryOp::Pipe,
+                            Expr::ident("upper")
+                        )
+                    ),
+                    Statement::expr(Expr::ident("rows")),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_line_starting_with_unary_operator() {
+        assert_eq!(
+            parse_program("let a = 5\n-a\n+a"),
+            Ok((
+                "",
+                Program::new(vec![
+                    Statement::let_binding("a", Expr::literal_number(5)),
+                    Statement::expr(Expr::unary_expr(UnaryOp::Minus, Expr::ident("a"))),
+                    Statement::expr(Expr::unary_expr(UnaryOp::Plus, Expr::ident("a"))),
+                ])
+            ))
+        );
+        let subtraction = Expr::binary_expr(Expr::ident("a"), BinaryOp::Sub, Expr::ident("b"));
+        assert_eq!(
+            parse_program("(a\n- b)\nf(a\n- b)\nif a\n- b > 0 then 1 else 2"),
+            Ok((
+                "",
+                Program::new(vec![
+                    Statement::expr(Expr::parenthesized(subtraction.clone())),
+                    Statement::expr(Expr::function_call("f", vec![subtraction.clone()])),
+                    Statement::expr(Expr::if_else(
+                        Expr::binary_expr(subtraction, BinaryOp::Gt, Expr::literal_number(0)),
+                        Expr::literal_number(1),
+                        Expr::literal_number(2)
+                    )),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_program("a - b\n  * c"),
+            Ok((
+                "",
+                Program::new(vec![Statement::expr(Expr::binary_expr(
+                    Expr::ident("a"),
+                    BinaryOp::Sub,
+                    Expr::binary_expr(Expr::ident("b"), BinaryOp::Mul, Expr::ident("c"))
+                ))])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_call_parenthesis_on_the_next_line() {
+        assert_eq!(
+            parse_program("f\n(b + 1)\ng (b)"),
+            Ok((
+                "",
+                Program::new(vec![
+                    Statement::expr(Expr::ident("f")),
+                    Statement::expr(Expr::parenthesized(Expr::binary_expr(
+                        Expr::ident("b"),
+                        BinaryOp::Add,
+                        Expr::literal_number(1)
+                    ))),
+                    Statement::expr(Expr::function_call("g", vec![Expr::ident("b")])),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_step_as_a_name() {
+        assert_eq!(
+            parse_program("let step = 2; step"),
+            Ok((
+                "",
+                Program::new(vec![
+                    Statement::let_binding("step", Expr::literal_number(2)),
+                    Statement::expr(Expr::ident("step")),
+                ])
+            ))
+        );
+        let range = |step| Expr::range(Expr::literal_number(0), Expr::ident("n"), false, step);
+        assert_eq!(
+            parse_program("def step(x) = x\nlet r = 0..n\nstep(r)\n(0..n\n  step 2)"),
+            Ok((
+                "",
+                Program::new(vec![
+                    Statement::def("step", vec!["x"], Expr::ident("x")),
+                    Statement::let_binding("r", range(None)),
+                    Statement::expr(Expr::function_call("step", vec![Expr::ident("r")])),
+                    Statement::expr(Expr::parenthesized(range(Some(Expr::literal_number(2))))),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_let_prefix_is_an_identifier() {
+        assert_eq!(
+            parse_program("letter"),
+            Ok((
+                "",
+                Program::new(vec![Statement::expr(Expr::ident("letter"))])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_statements_on_one_line_need_a_separator() {
+        assert_eq!(
+            parse_program("1 2"),
+            Ok((
+                "2",
+                Program::new(vec![Statement::expr(Expr::literal_number(1))])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_def() {
+        assert_eq!(
+            parse_program("def clean(s) = trim(lower(s))\nclean(name)"),
+            Ok((
+                "",
+                Program::new(vec![
+                    Statement::def(
+                        "clean",
+                        vec!["s"],
+                        Expr::function_call(
+                            "trim",
+                            vec![Expr::function_call("lower", vec![Expr::ident("s")])]
+                        )
+                    ),
+                    Statement::expr(Expr::function_call("clean", vec![Expr::ident("name")])),
+                ])
+            ))
+        );
+        assert!(matches!(
+            parse_program("def f(x, x) = x"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(matches!(
+            parse_program("def f = 1"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert_eq!(
+            parse_program("default"),
+            Ok((
+                "",
+                Program::new(vec![Statement::expr(Expr::ident("default"))])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_let() {
+        assert!(matches!(
+            parse_program("let = 1"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(matches!(
+            parse_program("let x 1"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_statement_spans_and_comments() {
+        let input = "# setup\nlet x = f( # inner\n  1)\nx + 2 # result\n# done";
+        let (_, program) = parse_program(input).unwrap();
+        let [first, second] = program.statements.as_slice() else {
+            panic!("expected two statements, got {:?}", program);
+        };
+        assert_eq!((first.span.start, first.span.end), (8, 31));
+        assert_eq!((second.span.line, second.span.column), (4, 1));
+        let StatementKind::Let(name, value) = &first.kind else {
+            panic!("expected a let binding, got {:?}", first);
+        };
+        assert_eq!((name.span.start, name.span.end), (12, 13));
+        let texts = |comments: &[crate::ast::Comment]| {
+            comments.iter().map(|c| c.text.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(texts(&first.trivia.leading), vec![" setup"]);
+        assert!(first.trivia.trailing.is_empty());
+        assert!(value.trivia.is_empty());
+        let ExprKind::FunctionCall(_, args, _) = &value.kind else {
+            panic!("expected a function call, got {:?}", value);
+        };
+        assert_eq!(texts(&args[0].trivia.leading), vec![" inner"]);
+        assert!(second.trivia.leading.is_empty());
+        assert_eq!(texts(&second.trivia.trailing), vec![" result", " done"]);
+        assert!(second.value().trivia.is_empty());
+    }
+
+    #[test]
+    fn test_parse_comments_around_nested_nodes() {
+        let input = "f(1, # one\n  2 # two\n  # left over\n) # call\nnext # last\n# end";
+        let (_, program) = parse_program(input).unwrap();
+        let [call, next] = program.statements.as_slice() else {
+            panic!("expected two statements, got {:?}", program);
+        };
+        let texts = |comments: &[crate::ast::Comment]| {
+            comments.iter().map(|c| c.text.clone()).collect::<Vec<_>>()
+        };
+        let ExprKind::FunctionCall(_, args, _) = &call.value().kind else {
+            panic!("expected a function call, got {:?}", call);
+        };
+        assert_eq!(texts(&args[0].trivia.trailing), vec![" one"]);
+        assert_eq!(texts(&args[1].trivia.trailing), vec![" two"]);
+        assert!(call.value().trivia.is_empty());
+        assert_eq!(texts(&call.trivia.trailing), vec![" call"]);
+        assert_eq!(texts(&next.trivia.leading), vec![" left over"]);
+        assert_eq!(texts(&next.trivia.trailing), vec![" last", " end"]);
+    }
+
+    #[test]
+    fn test_statements_compare_comments() {
+        assert_eq!(
+            parse_program("# a\nlet x = 1").unwrap().1,
+            parse_program("# a\n\nlet x = 1").unwrap().1
+        );
+        assert_ne!(
+            parse_program("# a\nlet x = 1").unwrap().1,
+            parse_program("# b\nlet x = 1").unwrap().1
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use nom::bytes::complete::tag;
+use nom::bytes::complete::take_while;
+use nom::character::complete::satisfy;
+use nom::combinator::cut;
+use nom::combinator::map_res;
+use nom::combinator::not;
+use nom::error::context;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
+use nom::sequence::tuple;
+use unicode_ident::is_xid_continue;
+
+use super::string::parse_raw_string;
+use super::IResult;
+use crate::ast::Regex;
+
+/// Parses a regex literal: `~` followed by a raw string (`~r"^\d+$"`, or `~r#"say "hi""#` to
+/// include a `"`) and any of the flags `i`, `m`, `s` and `x`.
+/// The regex is compiled here, so once the `~` has been seen an invalid pattern is a failure,
+/// reported as a syntax error.
+pub fn parse_regex(input: &str) -> IResult<&str, Regex> {
+    preceded(
+        tag("~"),
+        cut(context(
+            "regular expression",
+            map_res(
+                tuple((
+                    parse_raw_string,
+                    terminated(
+                        take_while(|c| matches!(c, 'i' | 'm' | 's' | 'x')),
+                        not(satisfy(is_xid_continue)),
+                    ),
+                )),
+                |(pattern, flags)| Regex::new(&pattern, flags),
+            ),
+        )),
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::error::VerboseErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_regex() {
+        let (rest, regex) = parse_regex(r#"~r"^[a-z]+$" + 1"#).unwrap();
+        assert_eq!(rest, " + 1");
+        assert_eq!(regex, Regex::new("^[a-z]+$", "").unwrap());
+        let (_, regex) = parse_regex(r##"~r#"say "(hi|bye)""#i"##).unwrap();
+        assert!(regex.is_match(r#"they SAY "HI""#));
+    }
+
+    #[test]
+    fn test_parse_invalid_regex() {
+        for input in [r#"~r"(""#, r#"~r"a"q"#, r#"~"a""#, "~r\"open"] {
+            let Err(nom::Err::Failure(e)) = parse_regex(input) else {
+                panic!("{} should fail", input);
+            };
+            assert!(
+                e.errors
+                    .contains(&(&input[1..], VerboseErrorKind::Context("regular expression"))),
+                "{input}"
+            );
+        }
+    }
+}
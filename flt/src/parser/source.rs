@@ -0,0 +1,183 @@
+use nom::error::ErrorKind;
+use nom::error::VerboseError;
+use nom::error::VerboseErrorKind;
+use nom::Offset;
+
+use crate::ast::Span;
+use crate::Error;
+
+/// The complete text being parsed, indexed by line so that spans can be located cheaply.
+///
+/// Every parser consumes a suffix of the source, so a byte offset is recovered from the
+/// length of the remaining input alone.
+pub(crate) struct Source<'s> {
+    text: &'s str,
+    line_starts: Vec<usize>,
+}
+
+impl<'s> Source<'s> {
+    pub fn new(text: &'s str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    pub fn text(&self) -> &'s str {
+        self.text
+    }
+
+    /// The byte offset at which `rest`, a suffix of the source, begins.
+    pub fn offset(&self, rest: &str) -> usize {
+        self.text.len() - rest.len()
+    }
+
+    /// The span between two suffixes of the source: from where `start` begins to where `end` begins.
+    pub fn span(&self, start: &str, end: &str) -> Span {
+        let start = self.offset(start);
+        let (line, column) = self.locate(start);
+        Span::new(start, self.offset(end), line, column)
+    }
+
+    /// The span of `fragment`, a slice of the source.
+    pub fn span_of(&self, fragment: &str) -> Span {
+        let start = self.text.offset(fragment);
+        let (line, column) = self.locate(start);
+        Span::new(start, start + fragment.len(), line, column)
+    }
+
+    /// The 1-based line and column of the byte `offset`.
+    fn locate(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&s| s <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.text[line_start..offset].chars().count() + 1;
+        (line, column)
+    }
+
+    /// Renders the line containing `span` with a caret underneath its first character:
+    ///
+    /// ```text
+    ///   |
+    /// 2 | HEAD(10) |> )
+    ///   |             ^
+    /// ```
+    pub fn snippet(&self, span: Span) -> String {
+        let line_start = self.line_starts[span.line - 1];
+        let line_end = self.text[line_start..]
+            .find('\n')
+            .map_or(self.text.len(), |i| line_start + i);
+        let text = self.text[line_start..line_end].trim_end_matches('\r');
+        let gutter = " ".repeat(span.line.to_string().len());
+        format!(
+            "{gutter} |\n{line} | {text}\n{gutter} | {pad}^",
+            line = span.line,
+            pad = " ".repeat(span.column - 1),
+        )
+    }
+
+    /// Describes the input at `rest` for an error message: the offending word or character, or end of input.
+    pub fn found(rest: &str) -> String {
+        let word = |c: char| c.is_alphanumeric() || c == '_';
+        match rest.chars().next() {
+            None => "end of input".to_string(),
+            Some(c) if word(c) => {
+                let end = rest.find(|c| !word(c)).unwrap_or(rest.len());
+                format!("'{}'", &rest[..end])
+            }
+            Some(c) => format!("{:?}", c),
+        }
+    }
+
+    /// Formats `message` with the location of `rest`, a suffix of the source, and a snippet pointing at it.
+    pub fn located(&self, message: impl std::fmt::Display, rest: &str) -> String {
+        let span = self.span(rest, rest);
+        format!("{} at {}\n{}", message, span, self.snippet(span))
+    }
+
+    /// Converts a nom error raised while parsing this source into a located [`Error`].
+    ///
+    /// The innermost error locates the failure, and the innermost context or expected
+    /// character names what the parser wanted there. Parsers expect characters with
+    /// `char` only where nothing else may follow (closing delimiters after a `cut`), and
+    /// use `tag` for alternatives, so that a failed alternative never masks the context.
+    pub fn error(&self, err: nom::Err<VerboseError<&str>>) -> Error {
+        let errors = match err {
+            nom::Err::Incomplete(_) => vec![],
+            nom::Err::Error(e) | nom::Err::Failure(e) => e.errors,
+        };
+        let rest = errors
+            .first()
+            .map_or(&self.text[self.text.len()..], |(input, _)| *input);
+        let expected = errors.iter().find_map(|(_, kind)| match kind {
+            VerboseErrorKind::Context(context) => Some(context.to_string()),
+            VerboseErrorKind::Char(c) => Some(format!("{:?}", c)),
+            VerboseErrorKind::Nom(_) => None,
+        });
+        let too_deep = errors
+            .iter()
+            .any(|(_, kind)| *kind == VerboseErrorKind::Nom(ErrorKind::TooLarge));
+        let message = match expected {
+            _ if too_deep => "nesting too deep".to_string(),
+            Some(expected) => format!("expected {}, found {}", expected, Self::found(rest)),
+            None => format!("unexpected {}", Self::found(rest)),
+        };
+        let message = self.located(message, rest);
+        if errors
+            .iter()
+            .any(|(_, kind)| *kind == VerboseErrorKind::Nom(ErrorKind::MapRes))
+        {
+            Error::SyntaxError(message)
+        } else {
+            Error::ParserError(message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_line_and_column() {
+        let text = "READ(\"a\") |>\n  HEAD(10)";
+        let source = Source::new(text);
+        let head = &text[text.find("HEAD").unwrap()..];
+        let span = source.span(head, &head[4..]);
+        assert_eq!((span.start, span.end), (15, 19));
+        assert_eq!((span.line, span.column), (2, 3));
+    }
+
+    #[test]
+    fn test_column_counts_characters() {
+        let text = "\"héllo\" + x";
+        let source = Source::new(text);
+        let x = &text[text.find('x').unwrap()..];
+        assert_eq!(source.span(x, "").column, 11);
+    }
+
+    #[test]
+    fn test_snippet() {
+        let text = "1 +\n  2 )";
+        let source = Source::new(text);
+        let span = source.span(")", "");
+        assert_eq!(span.line, 2);
+        assert_eq!(source.snippet(span), "  |\n2 |   2 )\n  |     ^");
+    }
+
+    #[test]
+    fn test_error() {
+        let text = "1 + )";
+        let source = Source::new(text);
+        let err = source.error(nom::Err::Failure(VerboseError {
+            errors: vec![
+                (&text[4..], VerboseErrorKind::Nom(ErrorKind::Tag)),
+                (&text[4..], VerboseErrorKind::Context("expression")),
+            ],
+        }));
+        assert!(matches!(
+            err,
+            Error::ParserError(message)
+                if message == "expected expression, found ')' at line 1, column 5\n  |\n1 | 1 + )\n  |     ^"
+        ));
+    }
+}
@@ -1,31 +1,229 @@
 use nom::branch::alt;
-use nom::bytes::complete::escaped_transform;
 use nom::bytes::complete::is_not;
 use nom::bytes::complete::tag;
-use nom::combinator::success;
-use nom::combinator::value;
+use nom::bytes::complete::take;
+use nom::bytes::complete::take_until;
+use nom::bytes::complete::take_while;
+use nom::bytes::complete::take_while_m_n;
+use nom::character::complete::char;
+use nom::combinator::cut;
+use nom::combinator::map;
+use nom::combinator::map_res;
+use nom::combinator::not;
+use nom::combinator::recognize;
+use nom::error::context;
+use nom::error::VerboseError;
+use nom::multi::fold_many0;
 use nom::sequence::delimited;
-use nom::IResult;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
+use nom::sequence::tuple;
+use nom::Offset;
 
-/// Parses a Rust-like string: `"..."` with support for escaped `\"` (yielding `"`) and `\\` (yielding `\`).
+use super::IResult;
+
+/// Parses a string literal in any of its forms:
+/// - quoted: `"..."`, with the escapes `\"`, `\\`, `\$`, `\n`, `\r`, `\t`, `\0` and `\u{XXXX}`;
+/// - raw: `r"..."` or `r#"..."#` (any number of `#`s), with no escapes;
+/// - multi-line: `"""..."""`, with the same escapes as quoted strings. A line break right after
+///   the opening quotes and a line holding only the closing quotes are dropped, and the
+///   indentation common to the remaining lines is stripped.
+///
+/// An unknown escape sequence is a failure, reported as a syntax error.
+/// `${` has no special meaning here; see [`parse_interpolated_string`].
 pub fn parse_string(input: &str) -> IResult<&str, String> {
-    delimited(
-        tag("\""),
+    alt((
+        parse_raw_string,
+        parse_multiline_string,
+        parse_quoted_string,
+    ))(input)
+}
+
+fn parse_quoted_string(input: &str) -> IResult<&str, String> {
+    delimited(tag("\""), parse_contents("\\\""), tag("\""))(input)
+}
+
+pub(super) fn parse_raw_string(input: &str) -> IResult<&str, String> {
+    let (rest, hashes) = delimited(tag("r"), take_while(|c| c == '#'), tag("\""))(input)?;
+    let closing = format!("\"{}", hashes);
+    let (rest, text) = cut(context(
+        "end of raw string",
+        terminated(take_until(closing.as_str()), tag(closing.as_str())),
+    ))(rest)?;
+    Ok((rest, text.to_string()))
+}
+
+fn parse_multiline_string(input: &str) -> IResult<&str, String> {
+    let (rest, body) = preceded(
+        tag("\"\"\""),
+        cut(context(
+            "end of multi-line string",
+            terminated(take_until("\"\"\""), tag("\"\"\"")),
+        )),
+    )(input)?;
+
+    let blank = |line: &str| line.trim().is_empty();
+    let mut lines: Vec<&str> = body
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    if lines.len() > 1 && blank(lines[0]) {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && blank(lines[lines.len() - 1]) {
+        lines.pop();
+    }
+    let indent = lines
+        .iter()
+        .filter(|line| !blank(line))
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+
+    let mut value = String::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        if i > 0 {
+            value.push('\n');
+        }
+        if blank(line) {
+            continue;
+        }
+        // Errors must locate suffixes of the input, not of the line.
+        let (_, text) = parse_contents("\\")(&line[indent..]).map_err(|e| {
+            e.map(|e| VerboseError {
+                errors: e
+                    .errors
+                    .into_iter()
+                    .map(|(at, kind)| (&input[input.offset(at)..], kind))
+                    .collect(),
+            })
+        })?;
+        value.push_str(&text);
+    }
+    Ok((rest, value))
+}
+
+/// A piece of an interpolated string: text, or an embedded expression.
+#[derive(Debug, PartialEq)]
+pub enum Segment<O> {
+    Text(String),
+    Hole(O),
+}
+
+/// Parses a quoted string whose contents may embed expressions in `${...}` holes, each parsed
+/// with `parse_hole` and surrounded by anything `ws` skips. A `$` not followed by `{` is plain
+/// text, and `\$` escapes one that is. Adjacent text is merged into a single segment.
+pub fn parse_interpolated_string<'a, F, O, W, T>(
+    parse_hole: F,
+    ws: W,
+) -> impl Fn(&'a str) -> IResult<&'a str, Vec<Segment<O>>>
+where
+    F: Fn(&'a str) -> IResult<&'a str, O>,
+    W: Fn(&'a str) -> IResult<&'a str, T>,
+{
+    move |input: &'a str| {
+        let text = |s: &str| Segment::Text(s.to_string());
+        let segments = fold_many0(
+            alt((
+                map(is_not("\\\"$"), text),
+                map(parse_escape, |c| Segment::Text(c.to_string())),
+                map(
+                    preceded(
+                        tag("${"),
+                        cut(terminated(delimited(&ws, &parse_hole, &ws), char('}'))),
+                    ),
+                    Segment::Hole,
+                ),
+                map(tag("$"), text),
+            )),
+            Vec::new,
+            |mut segments: Vec<Segment<O>>, segment| {
+                match (segments.last_mut(), segment) {
+                    (Some(Segment::Text(text)), Segment::Text(more)) => text.push_str(&more),
+                    (_, segment) => segments.push(segment),
+                }
+                segments
+            },
+        );
+        preceded(
+            not(tag("\"\"\"")),
+            delimited(tag("\""), segments, tag("\"")),
+        )(input)
+    }
+}
+
+/// A piece of string contents: a run of plain text, or the character an escape stands for.
+enum Fragment<'a> {
+    Text(&'a str),
+    Char(char),
+}
+
+/// Parses string contents up to the first character in `special` other than an escape's `\`.
+fn parse_contents<'a>(special: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, String> {
+    fold_many0(
         alt((
-            escaped_transform(
-                is_not("\\\""),
-                '\\',
-                alt((value("\"", tag("\"")), value("\\", tag("\\")))),
+            map(is_not(special), Fragment::Text),
+            map(parse_escape, Fragment::Char),
+        )),
+        String::new,
+        |mut value, fragment| {
+            match fragment {
+                Fragment::Text(text) => value.push_str(text),
+                Fragment::Char(c) => value.push(c),
+            }
+            value
+        },
+    )
+}
+
+/// Parses an escape sequence: `\` followed by `"`, `\`, `$`, `n`, `r`, `t`, `0` or `u{XXXX}`.
+fn parse_escape(input: &str) -> IResult<&str, char> {
+    preceded(
+        char('\\'),
+        cut(context(
+            "escape sequence",
+            map_res(
+                alt((
+                    recognize(tuple((
+                        char('u'),
+                        char('{'),
+                        take_while_m_n(1, 6, |c: char| c.is_ascii_hexdigit()),
+                        char('}'),
+                    ))),
+                    take(1usize),
+                )),
+                unescape,
             ),
-            value(String::new(), success(())),
         )),
-        tag("\""),
     )(input)
 }
 
+fn unescape(escape: &str) -> Result<char, String> {
+    match escape {
+        "\"" => Ok('"'),
+        "\\" => Ok('\\'),
+        "$" => Ok('$'),
+        "n" => Ok('\n'),
+        "r" => Ok('\r'),
+        "t" => Ok('\t'),
+        "0" => Ok('\0'),
+        _ => escape
+            .strip_prefix("u{")
+            .and_then(|hex| u32::from_str_radix(hex.trim_end_matches('}'), 16).ok())
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("invalid escape sequence '\\{}'", escape)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use nom::error::ErrorKind;
+    use nom::error::VerboseErrorKind;
+
     use super::*;
+    use crate::ast::Expr;
+    use crate::parser::expr::parse_expr;
+    use crate::parser::trivia::parse_trivia;
 
     #[test]
     fn test_parse_simple_string() {
@@ -52,4 +250,92 @@ mod tests {
     fn test_parse_empty_string() {
         assert_eq!(parse_string(r#""""#), Ok(("", "".to_string())));
     }
+
+    #[test]
+    fn test_parse_string_escapes() {
+        assert_eq!(
+            parse_string(r#""a\tb\r\n\0\u{e9}\u{1F600}""#),
+            Ok(("", "a\tb\r\n\0é😀".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_escape() {
+        for input in [r#""\q""#, r#""\u{110000}""#, r#""\u{}""#, r#""\u00e9""#] {
+            let Err(nom::Err::Failure(e)) = parse_string(input) else {
+                panic!("{} should fail", input);
+            };
+            assert_eq!(
+                e.errors[0],
+                (&input[2..], VerboseErrorKind::Nom(ErrorKind::MapRes))
+            );
+            assert!(e
+                .errors
+                .contains(&(&input[2..], VerboseErrorKind::Context("escape sequence"))));
+        }
+    }
+
+    #[test]
+    fn test_parse_raw_string() {
+        assert_eq!(
+            parse_string(r#"r"C:\path\n" + 1"#),
+            Ok((" + 1", r"C:\path\n".to_string()))
+        );
+        assert_eq!(
+            parse_string(r###"r#"say "hi"\d+"#"###),
+            Ok(("", r#"say "hi"\d+"#.to_string()))
+        );
+        assert!(matches!(
+            parse_string(r##"r#"open""##),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_multiline_string() {
+        let input =
+            "\"\"\"\n    SELECT *\n      FROM t\n\n    WHERE a = \\\"x\\\"\\t\n    \"\"\" + 1";
+        assert_eq!(
+            parse_string(input),
+            Ok((
+                " + 1",
+                "SELECT *\n  FROM t\n\nWHERE a = \"x\"\t".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_string(r#""""one "quoted" line""""#),
+            Ok(("", r#"one "quoted" line"#.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_interpolated_string() {
+        let parse = parse_interpolated_string(parse_expr, parse_trivia);
+        assert_eq!(
+            parse(r#""rows: ${ count } in ${file}, cost \${x} $5""#),
+            Ok((
+                "",
+                vec![
+                    Segment::Text("rows: ".to_string()),
+                    Segment::Hole(Expr::ident("count")),
+                    Segment::Text(" in ".to_string()),
+                    Segment::Hole(Expr::ident("file")),
+                    Segment::Text(", cost ${x} $5".to_string()),
+                ]
+            ))
+        );
+        assert_eq!(parse(r#""""#), Ok(("", vec![])));
+        assert!(matches!(parse(r#""a ${}""#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse(r#""a ${x""#), Err(nom::Err::Failure(_))));
+        assert!(matches!(parse(r#""""x""""#), Err(nom::Err::Error(_))));
+    }
+
+    #[test]
+    fn test_parse_multiline_invalid_escape() {
+        let input = "\"\"\"\n  ok\n  bad \\q\n\"\"\"";
+        let Err(nom::Err::Failure(e)) = parse_string(input) else {
+            panic!("an unknown escape should fail");
+        };
+        assert_eq!(e.errors[0].0, &input[input.find("q").unwrap()..]);
+    }
 }
@@ -2,25 +2,28 @@ use std::borrow::Cow;
 
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::bytes::complete::take_while1;
 use nom::combinator::map;
-use nom::IResult;
 
+use super::identifier::parse_identifier;
 use super::string::parse_string;
+use super::IResult;
 
 /// Parses a Ruby-like symbol: `:identifier` or `:"string"`.
-/// - Identifier form: `:` followed by one or more alphanumeric, hyphen, or underscore characters.
+/// - Identifier form: `:` followed by an identifier, as [`parse_identifier`] accepts.
 /// - String form: `:` followed by a quoted string `"..."` with escape support.
 ///
 /// Returns `Cow<str>`: borrowed slice for identifiers (no allocation), owned for quoted strings (handles escapes).
 pub fn parse_symbol(input: &str) -> IResult<&str, Cow<'_, str>> {
     let (input, _) = tag(":")(input)?;
+    parse_symbol_name(input)
+}
+
+/// Parses the name of a symbol, the part after its `:`: an identifier or a quoted string.
+/// Record keys and field names take the same forms.
+pub fn parse_symbol_name(input: &str) -> IResult<&str, Cow<'_, str>> {
     alt((
         map(parse_string, Cow::Owned),
-        map(
-            take_while1(|c: char| c.is_alphanumeric() || c == '-' || c == '_'),
-            Cow::Borrowed,
-        ),
+        map(parse_identifier, Cow::Borrowed),
     ))(input)
 }
 
@@ -36,6 +39,7 @@ mod tests {
         assert_eq!(parse_symbol(":foo-bar"), Ok(("", Cow::Borrowed("foo-bar"))));
         assert_eq!(parse_symbol(":foo_bar"), Ok(("", Cow::Borrowed("foo_bar"))));
         assert_eq!(parse_symbol(":abc123"), Ok(("", Cow::Borrowed("abc123"))));
+        assert_eq!(parse_symbol(":v-2"), Ok(("-2", Cow::Borrowed("v"))));
     }
 
     #[test]
@@ -51,4 +55,17 @@ mod tests {
         // Empty string symbol: :""
         assert_eq!(parse_symbol(":\"\""), Ok(("", Cow::Owned(String::new()))));
     }
+
+    #[test]
+    fn test_parse_symbol_name() {
+        assert_eq!(
+            parse_symbol_name("size: 3"),
+            Ok((": 3", Cow::Borrowed("size")))
+        );
+        assert_eq!(
+            parse_symbol_name(r#""order id": 1"#),
+            Ok((": 1", Cow::Owned("order id".into())))
+        );
+        assert!(parse_symbol_name(":foo").is_err());
+    }
 }
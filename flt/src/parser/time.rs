@@ -0,0 +1,205 @@
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::TimeDelta;
+use chrono::Utc;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::bytes::complete::take_while1;
+use nom::bytes::complete::take_while_m_n;
+use nom::character::complete::char;
+use nom::character::complete::one_of;
+use nom::character::complete::satisfy;
+use nom::combinator::cut;
+use nom::combinator::map_res;
+use nom::combinator::not;
+use nom::combinator::opt;
+use nom::combinator::recognize;
+use nom::error::context;
+use nom::multi::many1;
+use nom::sequence::pair;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
+use nom::sequence::tuple;
+use unicode_ident::is_xid_continue;
+
+use super::number::digits;
+use super::IResult;
+use crate::ast::Literal;
+
+/// Parses a date or timestamp literal: `@` followed by an ISO 8601 date (`@2024-01-31`), or a
+/// date and time (`@2024-01-31T12:00:00`), with optional fractional seconds and an optional
+/// UTC offset (`Z` or `+02:00`). A timestamp without an offset is in UTC, never local time, so
+/// `@2024-01-31T12:00:00` is `@2024-01-31T12:00:00Z` wherever it is evaluated.
+/// Once the `@` has been seen, anything else, including an impossible date, is a failure.
+pub fn parse_date_time(input: &str) -> IResult<&str, Literal> {
+    let number = |n| take_while_m_n(n, n, |c: char| c.is_ascii_digit());
+    let date = tuple((number(4), char('-'), number(2), char('-'), number(2)));
+    let time = tuple((
+        char('T'),
+        number(2),
+        char(':'),
+        number(2),
+        char(':'),
+        number(2),
+        opt(pair(char('.'), take_while1(|c: char| c.is_ascii_digit()))),
+    ));
+    let offset = alt((
+        tag("Z"),
+        recognize(tuple((one_of("+-"), number(2), char(':'), number(2)))),
+    ));
+    preceded(
+        tag("@"),
+        cut(context(
+            "date",
+            map_res(
+                recognize(pair(date, opt(pair(time, opt(offset))))),
+                to_literal,
+            ),
+        )),
+    )(input)
+}
+
+fn to_literal(text: &str) -> Result<Literal, chrono::ParseError> {
+    if !text.contains('T') {
+        return NaiveDate::parse_from_str(text, "%Y-%m-%d").map(Literal::Date);
+    }
+    let timestamp = if text.ends_with('Z') || text[10..].contains(['+', '-']) {
+        DateTime::parse_from_rfc3339(text)?.with_timezone(&Utc)
+    } else {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")?.and_utc()
+    };
+    Ok(Literal::Timestamp(timestamp))
+}
+
+/// Parses a duration literal: one or more counts, each followed by a unit: `w` (weeks), `d`
+/// (days), `h`, `m`, `s`, `ms`, `us` or `ns`, as in `90s`, `3d` or `1h30m`. A duration must not
+/// run on into an identifier, so `3days` is not one.
+pub fn parse_duration(input: &str) -> IResult<&str, TimeDelta> {
+    let unit = alt((
+        tag("ms"),
+        tag("us"),
+        tag("ns"),
+        tag("w"),
+        tag("d"),
+        tag("h"),
+        tag("m"),
+        tag("s"),
+    ));
+    map_res(
+        terminated(many1(pair(digits, unit)), not(satisfy(is_xid_continue))),
+        |parts: Vec<(&str, &str)>| {
+            parts
+                .into_iter()
+                .try_fold(TimeDelta::zero(), |total, (count, unit)| {
+                    count
+                        .replace('_', "")
+                        .parse::<i64>()
+                        .ok()
+                        .and_then(|count| to_duration(count, unit))
+                        .and_then(|part| total.checked_add(&part))
+                        .ok_or_else(|| format!("duration out of range: {}{}", count, unit))
+                })
+        },
+    )(input)
+}
+
+fn to_duration(count: i64, unit: &str) -> Option<TimeDelta> {
+    match unit {
+        "w" => TimeDelta::try_weeks(count),
+        "d" => TimeDelta::try_days(count),
+        "h" => TimeDelta::try_hours(count),
+        "m" => TimeDelta::try_minutes(count),
+        "s" => TimeDelta::try_seconds(count),
+        "ms" => TimeDelta::try_milliseconds(count),
+        "us" => Some(TimeDelta::microseconds(count)),
+        _ => Some(TimeDelta::nanoseconds(count)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone as _;
+    use nom::error::VerboseErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date_time("@2024-01-31 + 1d"),
+            Ok((
+                " + 1d",
+                Literal::Date(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let noon = Utc.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap();
+        for input in [
+            "@2024-01-31T12:00:00Z",
+            "@2024-01-31T14:00:00+02:00",
+            "@2024-01-31T11:00:00.000-01:00",
+        ] {
+            assert_eq!(
+                parse_date_time(input),
+                Ok(("", Literal::Timestamp(noon))),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            parse_date_time("@2024-01-31T12:00:00.25Z"),
+            Ok(("", Literal::Timestamp(noon + TimeDelta::milliseconds(250))))
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp_without_offset() {
+        let noon = Utc.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap();
+        assert_eq!(
+            parse_date_time("@2024-01-31T12:00:00"),
+            Ok(("", Literal::Timestamp(noon)))
+        );
+        assert_eq!(
+            parse_date_time("@2024-01-31T12:00:00.5 "),
+            Ok((" ", Literal::Timestamp(noon + TimeDelta::milliseconds(500))))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_date() {
+        for input in [
+            "@2024-02-30",
+            "@2024-1-31",
+            "@today",
+            "@2024-01-31T25:00:00Z",
+        ] {
+            let Err(nom::Err::Failure(e)) = parse_date_time(input) else {
+                panic!("{} should fail", input);
+            };
+            assert!(
+                e.errors
+                    .contains(&(&input[1..], VerboseErrorKind::Context("date"))),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Ok(("", TimeDelta::seconds(90))));
+        assert_eq!(parse_duration("3d)"), Ok((")", TimeDelta::days(3))));
+        assert_eq!(parse_duration("1h30m"), Ok(("", TimeDelta::minutes(90))));
+        assert_eq!(
+            parse_duration("1_500ms"),
+            Ok(("", TimeDelta::milliseconds(1500)))
+        );
+        assert_eq!(parse_duration("2w"), Ok(("", TimeDelta::days(14))));
+        assert_eq!(parse_duration("5us"), Ok(("", TimeDelta::microseconds(5))));
+        assert!(parse_duration("3days").is_err());
+        assert!(parse_duration("3").is_err());
+        assert!(parse_duration("99999999999999w").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use nom::bytes::complete::tag;
+use nom::character::complete::multispace0;
+use nom::character::complete::not_line_ending;
+use nom::combinator::recognize;
+use nom::multi::many0;
+use nom::sequence::pair;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
+
+use super::IResult;
+use crate::ast::Comment;
+use crate::ast::Expr;
+use crate::ast::Statement;
+use crate::ast::Trivia;
+
+/// Parses a line comment: `#` up to, but not including, the end of the line.
+/// Returns the whole comment, `#` included.
+pub fn parse_comment(input: &str) -> IResult<&str, &str> {
+    recognize(pair(tag("#"), not_line_ending))(input)
+}
+
+/// Skips whitespace and line comments, returning the comments in order.
+pub fn parse_trivia(input: &str) -> IResult<&str, Vec<&str>> {
+    terminated(many0(preceded(multispace0, parse_comment)), multispace0)(input)
+}
+
+/// Attaches each comment to the node it most plausibly belongs to, given `text`, the source
+/// that `roots` (in source order) were parsed from:
+///
+/// - a comment on the same line as the end of a node trails the outermost node ending
+///   closest before it;
+/// - otherwise it leads the outermost node starting closest after it;
+/// - a comment after everything else trails the last root, whichever line it is on.
+///
+/// `comments` must be in source order; the nodes are walked once, alongside them.
+pub(crate) fn attach_comments(roots: &mut [&mut Expr], comments: Vec<Comment>, text: &str) {
+    let mut attacher = Attacher::new(comments, text);
+    for i in 0..roots.len() {
+        let bound = roots.get(i + 1).map_or(usize::MAX, |next| next.span.start);
+        attacher.walk(roots[i], bound);
+    }
+    attacher.finish(roots.last_mut().map(|root| &mut root.trivia));
+}
+
+/// Attaches the comments between the statements of a program to the statements themselves, and
+/// the comments within a statement to its expressions, by the rules of [`attach_comments`].
+pub(crate) fn attach_statement_comments(
+    statements: &mut [Statement],
+    comments: Vec<Comment>,
+    text: &str,
+) {
+    let mut attacher = Attacher::new(comments, text);
+    for i in 0..statements.len() {
+        let bound = statements
+            .get(i + 1)
+            .map_or(usize::MAX, |next| next.span.start);
+        let statement = &mut statements[i];
+        let span = statement.span;
+        attacher.enter(&mut statement.trivia, span.start);
+        attacher.walk(statement.value_mut(), span.end);
+        attacher.exit(&mut statement.trivia, span.end, bound);
+    }
+    attacher.finish(statements.last_mut().map(|s| &mut s.trivia));
+}
+
+/// Hands out comments, in source order, to nodes as a walk enters and leaves them.
+struct Attacher<'t> {
+    /// The comments; a comment is taken once it has been attached.
+    comments: Vec<Option<Comment>>,
+    /// The first comment that may not have been attached yet.
+    next: usize,
+    text: &'t str,
+}
+
+impl<'t> Attacher<'t> {
+    fn new(comments: Vec<Comment>, text: &'t str) -> Self {
+        Self {
+            comments: comments.into_iter().map(Some).collect(),
+            next: 0,
+            text,
+        }
+    }
+
+    /// Walks `expr` and its subexpressions, where `bound` is the offset at which the next node
+    /// starts or the enclosing one ends.
+    fn walk(&mut self, expr: &mut Expr, bound: usize) {
+        let span = expr.span;
+        self.enter(&mut expr.trivia, span.start);
+        let mut previous: Option<&mut Expr> = None;
+        expr.kind.for_each_child_mut(|child| {
+            let start = child.span.start;
+            if let Some(previous) = previous.replace(child) {
+                self.walk(previous, start);
+            }
+        });
+        if let Some(last) = previous {
+            self.walk(last, span.end);
+        }
+        self.exit(&mut expr.trivia, span.end, bound);
+    }
+
+    /// Leads a node starting at `start` with the comments not yet attached that end before it.
+    fn enter(&mut self, trivia: &mut Trivia, start: usize) {
+        while let Some(slot) = self.comments.get_mut(self.next) {
+            match slot {
+                Some(comment) if comment.span.end > start => break,
+                Some(_) => trivia.leading.extend(slot.take()),
+                None => {}
+            }
+            self.next += 1;
+        }
+    }
+
+    /// Trails a node ending at `end` with the comments on that line before `bound`. When the
+    /// enclosing node ends there too, `bound` is `end` and they are left to it instead.
+    fn exit(&mut self, trivia: &mut Trivia, end: usize, bound: usize) {
+        let text = self.text;
+        for slot in &mut self.comments[self.next..] {
+            let Some(comment) = slot else { continue };
+            let start = comment.span.start;
+            if start < end {
+                continue;
+            }
+            if start >= bound || text[end..start].contains('\n') {
+                break;
+            }
+            trivia.trailing.extend(slot.take());
+        }
+    }
+
+    /// Trails the last node with the comments after everything else.
+    fn finish(self, last: Option<&mut Trivia>) {
+        if let Some(last) = last {
+            last.trailing.extend(self.comments.into_iter().flatten());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comment() {
+        assert_eq!(parse_comment("# a Number\n1"), Ok(("\n1", "# a Number")));
+        assert_eq!(parse_comment("#"), Ok(("", "#")));
+        assert!(parse_comment("1 # not yet").is_err());
+    }
+
+    #[test]
+    fn test_parse_trivia() {
+        assert_eq!(
+            parse_trivia("  # one\n\t# two\r\n  1"),
+            Ok(("1", vec!["# one", "# two"]))
+        );
+        assert_eq!(parse_trivia("1"), Ok(("1", vec![])));
+    }
+}